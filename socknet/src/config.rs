@@ -1,4 +1,5 @@
 use crate::{
+	connection,
 	endpoint::{self, Endpoint},
 	stream,
 };
@@ -8,6 +9,11 @@ pub struct Config {
 	pub endpoint: endpoint::Config,
 	pub address: SocketAddr,
 	pub stream_registry: Arc<stream::Registry>,
+	/// The base configuration for all streams,
+	/// which can be adjusted by each handler's app context.
+	pub stream_config: stream::Config,
+	/// The channel capacities of local connections (from the endpoint to itself).
+	pub local_config: connection::LocalConfig,
 }
 
 impl Config {
@@ -18,14 +24,18 @@ impl Config {
 			self.address,
 			self.endpoint.fingerprint()
 		);
+		let role = self.endpoint.role();
 		match self.endpoint {
 			endpoint::Config::Server(config) => {
 				let (endpoint, incoming) = quinn::Endpoint::server(config.core, self.address)?;
 				let endpoint = Arc::new(Endpoint::new(
 					endpoint,
+					role,
 					config.certificate,
 					config.private_key,
 					self.stream_registry,
+					self.stream_config,
+					self.local_config,
 				));
 				endpoint.spawn_connection_listener(incoming);
 				Ok(endpoint)
@@ -35,9 +45,12 @@ impl Config {
 				endpoint.set_default_client_config(config.core);
 				Ok(Arc::new(Endpoint::new(
 					endpoint,
+					role,
 					config.certificate,
 					config.private_key,
 					self.stream_registry,
+					self.stream_config,
+					self.local_config,
 				)))
 			}
 		}
@@ -8,10 +8,25 @@ pub use error::*;
 mod connection;
 pub use connection::*;
 
+#[doc(hidden)]
+mod close;
+pub use close::*;
+
 mod datagram;
 pub use datagram::*;
 
+#[doc(hidden)]
+mod extensions;
+pub use extensions::*;
+
+#[doc(hidden)]
+mod local_config;
+pub use local_config::*;
+
 pub mod active;
 pub use active::Active;
 
 pub mod opened;
+
+#[cfg(feature = "simulator")]
+pub mod simulator;
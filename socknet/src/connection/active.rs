@@ -1,5 +1,5 @@
 use crate::{
-	connection::Datagram,
+	connection::{CloseCode, Datagram},
 	stream::kind::{recv, send},
 	utility::PinFutureResultLifetime,
 };
@@ -18,5 +18,9 @@ pub trait Active {
 	fn open_uni<'a>(&'a self) -> PinFutureResultLifetime<'a, send::Ongoing>;
 	fn open_bi<'a>(&'a self) -> PinFutureResultLifetime<'a, (send::Ongoing, recv::Ongoing)>;
 	fn send_datagram(&self, datagram: Datagram) -> anyhow::Result<()>;
-	fn close(&self, code: u32, reason: &[u8]);
+	/// Prepares the send half of a bidirectional stream which the peer opened,
+	/// so it is subject to the same conditions as the streams this side opens.
+	fn accept_send(&self, _stream: &mut send::Ongoing) {}
+	/// Closes the connection, failing all of its streams and sending the code and (UTF-8) reason to the peer.
+	fn close(&self, code: CloseCode, reason: &str);
 }
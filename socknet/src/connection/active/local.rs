@@ -1,37 +1,75 @@
 use super::Active;
 use crate::{
-	connection::Datagram,
-	endpoint::Endpoint,
+	connection::{CloseCode, Datagram},
 	stream::{
 		kind::{
 			recv::{self, ongoing::local::Internal as RecvLocalOngoing},
 			send::{self, ongoing::local::Internal as SendLocalOngoing},
 		},
-		local::{AnyBox, Outgoing},
+		local::{self, AnyBox, CloseState, Outgoing},
 	},
 	utility::PinFutureResultLifetime,
 };
-use std::{net::SocketAddr, sync::Weak};
+use std::{net::SocketAddr, sync::Arc};
 
-pub struct Local {
-	pub(crate) endpoint: Weak<Endpoint>,
+/// The channels which deliver new streams and datagrams to one side of a local connection.
+#[derive(Clone)]
+pub(crate) struct Link {
 	pub(crate) uni_streams: Outgoing<RecvLocalOngoing>,
 	pub(crate) bi_streams: Outgoing<(SendLocalOngoing, RecvLocalOngoing)>,
 	pub(crate) datagrams: Outgoing<Vec<AnyBox>>,
 }
 
+impl Link {
+	/// Ends the stream listeners of the side this link delivers to.
+	fn close(&self, error: quinn::ConnectionError) {
+		let _ = self.uni_streams.try_send(Err(error.clone()));
+		let _ = self.bi_streams.try_send(Err(error.clone()));
+		let _ = self.datagrams.try_send(Err(error));
+		self.uni_streams.close();
+		self.bi_streams.close();
+		self.datagrams.close();
+	}
+}
+
+/// One side of an in-memory connection.
+///
+/// This is either an endpoint connected to itself (where both sides are the same connection),
+/// or one of a pair of connections linking two endpoints in the same process
+/// (see [`Endpoint::connect_in_memory`](crate::endpoint::Endpoint::connect_in_memory)).
+pub struct Local {
+	pub(crate) peer_address: SocketAddr,
+	pub(crate) peer_certificate: rustls::Certificate,
+	/// Delivers streams and datagrams opened by this side to the peer.
+	pub(crate) outgoing: Link,
+	/// Delivers streams and datagrams opened by the peer to this side, so closing this side can end them.
+	pub(crate) incoming: Link,
+	/// The number of items which can be queued in each stream before writes wait for the reader.
+	pub(crate) stream_capacity: usize,
+	/// Signals every stream of the connection (on both sides) when it is closed.
+	pub(crate) closed: Arc<tokio::sync::watch::Sender<CloseState>>,
+}
+
+impl Local {
+	fn closed(&self) -> local::Closed {
+		self.closed.subscribe().into()
+	}
+
+	fn ensure_open(&self) -> anyhow::Result<()> {
+		match local::closed_error(&self.closed.borrow()) {
+			Some(error) => Err(error)?,
+			None => Ok(()),
+		}
+	}
+}
+
 impl Active for Local {
 	fn remote_address(&self) -> SocketAddr {
-		self.endpoint.upgrade().unwrap().address()
+		self.peer_address
 	}
 
 	fn peer_identity(&self) -> Option<Box<dyn std::any::Any>> {
-		Some(Box::new(vec![self
-			.endpoint
-			.upgrade()
-			.unwrap()
-			.certificate()
-			.clone()]))
+		Some(Box::new(vec![self.peer_certificate.clone()]))
 	}
 
 	fn is_local(&self) -> bool {
@@ -40,37 +78,70 @@ impl Active for Local {
 
 	fn open_uni<'a>(&'a self) -> PinFutureResultLifetime<'a, send::Ongoing> {
 		Box::pin(async move {
-			let (send, recv) = async_channel::unbounded::<AnyBox>();
-			self.uni_streams.send(Ok(recv)).await?;
-			Ok(send.into())
+			self.ensure_open()?;
+			let (send, recv) = async_channel::bounded::<AnyBox>(self.stream_capacity);
+			let codes = local::Codes::default();
+			self.outgoing
+				.uni_streams
+				.send(Ok(RecvLocalOngoing(recv, self.closed(), codes.clone())))
+				.await?;
+			Ok(SendLocalOngoing(send, self.closed(), codes).into())
 		})
 	}
 
 	fn open_bi<'a>(&'a self) -> PinFutureResultLifetime<'a, (send::Ongoing, recv::Ongoing)> {
 		Box::pin(async move {
-			let (a_send, b_recv) = async_channel::unbounded::<AnyBox>();
-			let (b_send, a_recv) = async_channel::unbounded::<AnyBox>();
-			self.bi_streams.send(Ok((b_send, b_recv))).await?;
+			self.ensure_open()?;
+			let (a_send, b_recv) = async_channel::bounded::<AnyBox>(self.stream_capacity);
+			let (b_send, a_recv) = async_channel::bounded::<AnyBox>(self.stream_capacity);
+			let (a_to_b, b_to_a) = (local::Codes::default(), local::Codes::default());
+			self.outgoing
+				.bi_streams
+				.send(Ok((
+					SendLocalOngoing(b_send, self.closed(), b_to_a.clone()),
+					RecvLocalOngoing(b_recv, self.closed(), a_to_b.clone()),
+				)))
+				.await?;
 			Ok((
-				send::Ongoing::Local(a_send.into()),
-				recv::Ongoing::Local(a_recv.into()),
+				SendLocalOngoing(a_send, self.closed(), a_to_b).into(),
+				RecvLocalOngoing(a_recv, self.closed(), b_to_a).into(),
 			))
 		})
 	}
 
 	fn send_datagram(&self, datagram: Datagram) -> anyhow::Result<()> {
 		if let Datagram::Local(data) = datagram {
-			self.datagrams.try_send(Ok(data))?;
-			Ok(())
+			self.ensure_open()?;
+			match self.outgoing.datagrams.try_send(Ok(data)) {
+				// Like a remote datagram, the datagram is lost if the receiver cannot keep up.
+				Err(async_channel::TrySendError::Full(_)) => {
+					log::trace!(target: crate::LOG, "Dropped local datagram, the queue is full");
+					Ok(())
+				}
+				result => Ok(result?),
+			}
 		} else {
 			unimplemented!()
 		}
 	}
 
-	fn close(&self, _code: u32, _reason: &[u8]) {
-		// NO-OP: Local connections mean the application is sending data to itself
+	fn close(&self, code: CloseCode, reason: &str) {
+		if self.closed.borrow().is_some() {
+			return;
+		}
+		// Local connections mean the application is sending data to itself
 		// (Client-On-Top-Of-Server: a situation where the user is hosting a game
 		// and the app is both the server for other clients and a client itself),
-		// so "closing" this connection has no meaning. You cannot close a connection to yourself.
+		// or to another endpoint in the same process.
+		// Closing the connection ends all of its streams (failing any pending reads and writes),
+		// and ends the incoming stream listeners of both sides,
+		// so the connection is dropped like a remote one would be.
+		self.closed.send_replace(Some((code, reason.to_owned())));
+		let error = quinn::ConnectionError::ApplicationClosed(quinn::ApplicationClose {
+			error_code: code.into(),
+			reason: bytes::Bytes::copy_from_slice(reason.as_bytes()),
+		});
+		self.outgoing.close(error.clone());
+		self.incoming.close(error);
 	}
 }
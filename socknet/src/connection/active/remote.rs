@@ -1,6 +1,6 @@
 use super::Active;
 use crate::{
-	connection::Datagram,
+	connection::{CloseCode, Datagram},
 	stream::kind::{recv, send},
 	utility::PinFutureResultLifetime,
 };
@@ -47,7 +47,7 @@ impl Active for Remote {
 		}
 	}
 
-	fn close(&self, code: u32, reason: &[u8]) {
-		self.0.close(code.into(), reason);
+	fn close(&self, code: CloseCode, reason: &str) {
+		self.0.close(code.into(), reason.as_bytes());
 	}
 }
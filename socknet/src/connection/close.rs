@@ -0,0 +1,137 @@
+/// The reason a connection was [`closed`](crate::connection::Active::close), as sent to the peer.
+///
+/// Socknet reserves the codes below [`APPLICATION_START`](Self::APPLICATION_START) for its own reasons,
+/// and the next 2^32 codes are free for the application to define.
+///
+/// Close codes are sent as 62-bit varints, while stream codes are limited to 32 bits
+/// (see [`code`](crate::stream::kind::code)), so the close codes reserve a wider range
+/// (which also leaves the application a full 32 bits of its own) than the stream codes do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CloseCode {
+	/// The connection (or its endpoint) was shut down normally.
+	Shutdown,
+	/// The peer sent data which does not follow the socknet protocol.
+	ProtocolViolation,
+	/// The peers run incompatible versions of the application.
+	VersionMismatch,
+	/// The peer is not allowed to connect.
+	Unauthorized,
+	/// The peer did not respond in time.
+	Timeout,
+	/// The peer was removed by the application (such as a server kicking a client).
+	Kicked,
+	/// A code which is reserved by socknet, but unknown to this version.
+	///
+	/// A reserved code of one of the named reasons (such as `Reserved(5)`) is sent as that reason,
+	/// so it is [`normalized`](Self::normalized) to it when the connection is closed.
+	Reserved(u32),
+	/// A code defined by the application, which is sent as [`APPLICATION_START`](Self::APPLICATION_START) plus the value.
+	Application(u32),
+	/// A code received from a peer which is beyond the application range, so socknet never sends it.
+	/// The code is kept as it was sent.
+	Unknown(u64),
+}
+
+impl CloseCode {
+	/// The first code of the range which the application can define.
+	pub const APPLICATION_START: u64 = 1 << 32;
+
+	/// The code which is sent to the peer.
+	pub fn into_inner(self) -> u64 {
+		match self {
+			Self::Shutdown => 0,
+			Self::ProtocolViolation => 1,
+			Self::VersionMismatch => 2,
+			Self::Unauthorized => 3,
+			Self::Timeout => 4,
+			Self::Kicked => 5,
+			Self::Reserved(code) => code as u64,
+			Self::Application(code) => Self::APPLICATION_START + code as u64,
+			Self::Unknown(code) => code,
+		}
+	}
+
+	/// Returns the code as the peer decodes it, replacing any reserved code of a named reason with that reason.
+	pub fn normalized(self) -> Self {
+		Self::from(self.into_inner())
+	}
+}
+
+impl From<u64> for CloseCode {
+	fn from(code: u64) -> Self {
+		match code {
+			0 => Self::Shutdown,
+			1 => Self::ProtocolViolation,
+			2 => Self::VersionMismatch,
+			3 => Self::Unauthorized,
+			4 => Self::Timeout,
+			5 => Self::Kicked,
+			code if code < Self::APPLICATION_START => Self::Reserved(code as u32),
+			code if code - Self::APPLICATION_START <= u32::MAX as u64 => {
+				Self::Application((code - Self::APPLICATION_START) as u32)
+			}
+			code => Self::Unknown(code),
+		}
+	}
+}
+
+impl From<CloseCode> for quinn::VarInt {
+	fn from(code: CloseCode) -> Self {
+		// Every code socknet sends is at most 2^33, and unknown codes were received as varints,
+		// but an unknown code built by the application can exceed the range, so it is clamped.
+		quinn::VarInt::from_u64(code.into_inner()).unwrap_or(quinn::VarInt::MAX)
+	}
+}
+
+impl From<quinn::VarInt> for CloseCode {
+	fn from(code: quinn::VarInt) -> Self {
+		Self::from(code.into_inner())
+	}
+}
+
+impl std::fmt::Display for CloseCode {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Shutdown => write!(f, "Shutdown"),
+			Self::ProtocolViolation => write!(f, "Protocol violation"),
+			Self::VersionMismatch => write!(f, "Version mismatch"),
+			Self::Unauthorized => write!(f, "Unauthorized"),
+			Self::Timeout => write!(f, "Timed out"),
+			Self::Kicked => write!(f, "Kicked"),
+			Self::Reserved(code) => write!(f, "Reserved code {}", code),
+			Self::Application(code) => write!(f, "Application code {}", code),
+			Self::Unknown(code) => write!(f, "Unknown code {}", code),
+		}
+	}
+}
+
+/// How a connection was closed, reported by the [`Closed`](crate::connection::event::Event::Closed) event.
+///
+/// Displays as the code followed by the reason (such as "Kicked: spamming").
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Closed {
+	pub code: CloseCode,
+	pub reason: String,
+	/// If the peer closed the connection, rather than this side.
+	pub by_peer: bool,
+}
+
+impl Closed {
+	/// Decodes the close frame sent by a peer, replacing any reason which is not valid UTF-8.
+	pub(crate) fn from_peer(code: quinn::VarInt, reason: &[u8]) -> Self {
+		Self {
+			code: code.into(),
+			reason: String::from_utf8_lossy(reason).into_owned(),
+			by_peer: true,
+		}
+	}
+}
+
+impl std::fmt::Display for Closed {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self.reason.is_empty() {
+			true => write!(f, "{}", self.code),
+			false => write!(f, "{}: {}", self.code, self.reason),
+		}
+	}
+}
@@ -1,6 +1,9 @@
 use crate::{
-	connection::{active::Active, event::Event, opened::Opened, Datagram, Error},
-	endpoint::Endpoint,
+	connection::{
+		active::Active, event::Event, opened::Opened, CloseCode, Closed, Datagram, Error,
+		Extensions,
+	},
+	endpoint::{self, Endpoint},
 	utility::JoinHandleList,
 };
 use crate::{
@@ -12,17 +15,74 @@ use crate::{
 };
 use std::{
 	net::SocketAddr,
-	sync::{Arc, Weak},
+	sync::{Arc, Mutex, RwLock, Weak},
 };
 
+/// The registry and stream configuration of a connection which has no [`Endpoint`],
+/// like the connections of a [`MockConnection`](crate::testing::MockConnection).
+pub(crate) struct Standalone {
+	pub(crate) registry: Arc<stream::Registry>,
+	pub(crate) stream_config: stream::Config,
+}
+
 pub struct Connection {
 	pub(crate) endpoint: Weak<Endpoint>,
 	pub(crate) connection: Box<dyn Active + Send + Sync + 'static>,
 	#[allow(dead_code)]
 	pub(crate) handles: Arc<JoinHandleList>,
+	/// Used in place of the endpoint for connections which do not have one.
+	pub(crate) standalone: Option<Standalone>,
+	/// The registry which replaces the endpoint's registry for this connection, if any.
+	scope: RwLock<Option<Arc<stream::Registry>>>,
+	extensions: Extensions,
+	/// How the connection was closed, once either side has closed it.
+	closed: Mutex<Option<Closed>>,
 }
 
 impl Connection {
+	pub(crate) fn new(
+		endpoint: Weak<Endpoint>,
+		connection: Box<dyn Active + Send + Sync + 'static>,
+		handles: Arc<JoinHandleList>,
+	) -> Self {
+		#[cfg(feature = "simulator")]
+		let connection = match endpoint.upgrade().and_then(|endpoint| endpoint.simulator()) {
+			Some(simulator) => simulator.wrap(connection),
+			None => connection,
+		};
+		Self {
+			endpoint,
+			connection,
+			handles,
+			standalone: None,
+			scope: RwLock::new(None),
+			extensions: Extensions::default(),
+			closed: Mutex::new(None),
+		}
+	}
+
+	/// Creates a connection which does not belong to an endpoint,
+	/// using the provided registry and stream configuration instead of the endpoint's.
+	#[cfg_attr(not(feature = "testing"), allow(dead_code))]
+	pub(crate) fn standalone(
+		connection: Box<dyn Active + Send + Sync + 'static>,
+		registry: Arc<stream::Registry>,
+		stream_config: stream::Config,
+	) -> Self {
+		Self {
+			endpoint: Weak::new(),
+			connection,
+			handles: Arc::new(JoinHandleList::new()),
+			standalone: Some(Standalone {
+				registry,
+				stream_config,
+			}),
+			scope: RwLock::new(None),
+			extensions: Extensions::default(),
+			closed: Mutex::new(None),
+		}
+	}
+
 	pub(crate) fn create<T>(endpoint: &Arc<Endpoint>, opened: T) -> Weak<Self>
 	where
 		T: Opened,
@@ -52,12 +112,98 @@ impl Connection {
 		Ok(crate::utility::fingerprint(&certificate))
 	}
 
+	/// Application state attached to the connection, which is dropped with the connection.
+	pub fn extensions(&self) -> &Extensions {
+		&self.extensions
+	}
+
 	pub fn endpoint(&self) -> anyhow::Result<Arc<Endpoint>> {
 		Endpoint::upgrade(&self.endpoint)
 	}
 
+	/// Returns the registry of handlers which can be used on this connection (its current scope),
+	/// which is the registry of the endpoint unless it has been [`replaced`](Self::set_registry).
+	///
+	/// Incoming streams for handlers which are not in the registry are rejected,
+	/// and [`Initiators`](stream::handler::Initiator) find their identifiers in it.
 	pub fn registry(&self) -> anyhow::Result<Arc<stream::Registry>> {
-		Ok(self.endpoint()?.stream_registry.clone())
+		match self.scope() {
+			Some(registry) => Ok(registry),
+			None => self.base_registry(),
+		}
+	}
+
+	/// The registry which replaces the registry of the endpoint for this connection, if any.
+	pub(crate) fn scope(&self) -> Option<Arc<stream::Registry>> {
+		self.scope.read().unwrap().clone()
+	}
+
+	/// The registry of the endpoint (or standalone connection), regardless of the connection's scope.
+	pub(crate) fn base_registry(&self) -> anyhow::Result<Arc<stream::Registry>> {
+		match &self.standalone {
+			Some(standalone) => Ok(standalone.registry.clone()),
+			None => Ok(self.endpoint()?.stream_registry.clone()),
+		}
+	}
+
+	/// The role of the peer, which is the opposite of the endpoint's [`role`](Endpoint::role),
+	/// unless the endpoint is connected to itself.
+	///
+	/// Connections which do not have an endpoint have no known peer role.
+	pub fn peer_role(&self) -> Option<endpoint::Role> {
+		let endpoint = self.endpoint().ok()?;
+		match self.is_to_itself() {
+			true => Some(endpoint.role()),
+			false => Some(endpoint.role().peer()),
+		}
+	}
+
+	/// Whether the endpoint of the connection is connected to itself (so it is its own peer).
+	pub(crate) fn is_to_itself(&self) -> bool {
+		match self.endpoint() {
+			Ok(endpoint) => self.remote_address() == endpoint.address(),
+			Err(_) => false,
+		}
+	}
+
+	/// Replaces the registry of handlers which can be used on this connection,
+	/// such as switching from a registry with only login handlers to the full registry once the peer is authenticated.
+	/// Passing `None` restores the registry of the endpoint.
+	///
+	/// Streams which have already been handed to a handler are unaffected.
+	/// The [`interceptors`](stream::Registry::intercept) of the endpoint's registry still apply to the connection,
+	/// followed by those of the replacement.
+	pub fn set_registry(&self, registry: Option<Arc<stream::Registry>>) {
+		*self.scope.write().unwrap() = registry;
+	}
+
+	/// Returns how the connection was closed (by either side), if it has been closed.
+	pub fn close_reason(&self) -> Option<Closed> {
+		self.closed.lock().unwrap().clone()
+	}
+
+	/// Records how the connection was closed and reports it to the endpoint,
+	/// unless it was already closed (such as by this side, before the peer's close arrived).
+	fn record_close(&self, closed: Closed) {
+		{
+			let mut current = self.closed.lock().unwrap();
+			if current.is_some() {
+				return;
+			}
+			*current = Some(closed.clone());
+		}
+		if let Ok(endpoint) = self.endpoint() {
+			endpoint.send_connection_event(Event::Closed(self.remote_address(), closed));
+		}
+	}
+
+	/// Returns the [`stream configuration`](stream::Config) of the connection's endpoint,
+	/// which applies to all streams before their handlers adjust it.
+	pub fn stream_config(&self) -> anyhow::Result<stream::Config> {
+		match &self.standalone {
+			Some(standalone) => Ok(standalone.stream_config.clone()),
+			None => Ok(self.endpoint()?.stream_config().clone()),
+		}
 	}
 }
 
@@ -86,7 +232,19 @@ impl Active for Connection {
 		self.connection.send_datagram(datagram)
 	}
 
-	fn close(&self, code: u32, reason: &[u8]) {
+	fn accept_send(&self, stream: &mut send::Ongoing) {
+		self.connection.accept_send(stream);
+	}
+
+	/// Also reports the close to this side's endpoint as a [`Closed`](Event::Closed) event,
+	/// with the code [`normalized`](CloseCode::normalized) to the one which the peer receives.
+	fn close(&self, code: CloseCode, reason: &str) {
+		let code = code.normalized();
+		self.record_close(Closed {
+			code,
+			reason: reason.to_owned(),
+			by_peer: false,
+		});
 		self.connection.close(code, reason);
 	}
 }
@@ -126,13 +284,12 @@ impl Connection {
 		let log_target = format!("{}[{} streams]", self.log_target(), kind);
 		crate::utility::spawn(log_target.clone(), async move {
 			use futures_util::StreamExt;
-			use quinn::{ApplicationClose, ConnectionError};
+			use quinn::ConnectionError;
 			let mut close_cause = None;
 			while let Some(status) = incoming.next().await {
 				match status {
 					Ok(item) => {
-						let registry = self.endpoint()?.stream_registry.clone();
-						registry.create_receiver(self.clone(), item.into());
+						stream::Registry::create_receiver(self.clone(), item.into());
 					}
 					Err(error) => {
 						close_cause = Some(error);
@@ -141,27 +298,61 @@ impl Connection {
 				}
 			}
 
-			let cause = match close_cause {
-				Some(error) => {
-					if let ConnectionError::ApplicationClosed(ApplicationClose {
-						error_code, ..
-					}) = &error
-					{
-						format!("by client with exit code {}", error_code)
-					} else {
-						format!("due to error: {:?}", error)
-					}
-				}
+			let cause = match &close_cause {
+				Some(ConnectionError::ApplicationClosed(_)) => "by peer".to_owned(),
+				Some(ConnectionError::TimedOut) => "due to timeout".to_owned(),
+				Some(error) => format!("due to error: {:?}", error),
 				None => "naturally".to_owned(),
 			};
-
-			log::trace!(target: &log_target, "Incoming stream closed {}", cause);
+			let closed = match close_cause {
+				Some(error) => Self::closed_by_error(error),
+				// The streams only end without an error once this side closed the connection.
+				None => Closed {
+					code: CloseCode::Shutdown,
+					reason: String::new(),
+					by_peer: false,
+				},
+			};
+			log::trace!(target: &log_target, "Incoming stream closed {} ({})", cause, closed);
+			self.record_close(closed);
 
 			Ok(())
 		});
 	}
 }
 
+impl Connection {
+	/// Describes how the connection was closed from the error which ended its incoming streams.
+	/// Errors below the application (such as transport errors) use the closest socknet code, with the error as the reason.
+	fn closed_by_error(error: quinn::ConnectionError) -> Closed {
+		use quinn::ConnectionError;
+		let (code, by_peer) = match &error {
+			ConnectionError::ApplicationClosed(close) => {
+				return Closed::from_peer(close.error_code, &close.reason);
+			}
+			ConnectionError::TimedOut => {
+				return Closed {
+					code: CloseCode::Timeout,
+					reason: String::new(),
+					by_peer: false,
+				};
+			}
+			ConnectionError::VersionMismatch => (CloseCode::VersionMismatch, false),
+			// The peer closed the connection at the transport level, because this side broke the protocol.
+			ConnectionError::ConnectionClosed(_) => (CloseCode::ProtocolViolation, true),
+			ConnectionError::TransportError(_) => (CloseCode::ProtocolViolation, false),
+			// The peer lost the connection's state (such as by restarting) without closing it.
+			ConnectionError::Reset => (CloseCode::Shutdown, true),
+			ConnectionError::LocallyClosed => (CloseCode::Shutdown, false),
+		};
+		Closed {
+			code,
+			reason: error.to_string(),
+			by_peer,
+		}
+	}
+}
+
 impl Drop for Connection {
 	fn drop(&mut self) {
 		log::info!(
@@ -14,3 +14,36 @@ impl From<Vec<AnyBox>> for Datagram {
 		Self::Local(data)
 	}
 }
+
+#[cfg_attr(not(feature = "simulator"), allow(dead_code))]
+impl Datagram {
+	/// The number of bytes in the datagram.
+	/// Local datagrams only count the items which are bytes (i.e. when they were serialized).
+	pub(crate) fn len(&self) -> usize {
+		match self {
+			Self::Serialized(bytes) => bytes.len(),
+			Self::Local(items) => items
+				.iter()
+				.filter_map(|item| (**item).as_any().downcast_ref::<Vec<u8>>())
+				.map(|bytes| bytes.len())
+				.sum(),
+		}
+	}
+
+	/// Copies the datagram, if possible.
+	/// Local datagrams can only be copied if all of their items are bytes (i.e. when they were serialized),
+	/// because other values cannot be cloned.
+	pub(crate) fn try_clone(&self) -> Option<Self> {
+		match self {
+			Self::Serialized(bytes) => Some(Self::Serialized(bytes.clone())),
+			Self::Local(items) => {
+				let mut copies: Vec<AnyBox> = Vec::with_capacity(items.len());
+				for item in items.iter() {
+					let bytes = (**item).as_any().downcast_ref::<Vec<u8>>()?;
+					copies.push(Box::new(bytes.clone()));
+				}
+				Some(Self::Local(copies))
+			}
+		}
+	}
+}
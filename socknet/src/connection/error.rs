@@ -8,4 +8,9 @@ pub enum Error {
 	IdentityIsNotCertificate,
 	#[error("Connection's identity certificate list is empty.")]
 	CertificateIdentityIsEmpty,
+	#[error("Connection was closed ({code}: {reason}).")]
+	ConnectionClosed {
+		code: super::CloseCode,
+		reason: String,
+	},
 }
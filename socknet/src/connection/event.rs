@@ -1,4 +1,4 @@
-use crate::connection::{active::Active, Connection};
+use crate::connection::{active::Active, Closed, Connection};
 use std::{net::SocketAddr, sync::Weak};
 
 pub type Sender = async_channel::Sender<Event>;
@@ -7,6 +7,21 @@ pub type Receiver = async_channel::Receiver<Event>;
 pub enum Event {
 	Created(Weak<Connection>),
 	Dropped(SocketAddr),
+	/// A stream from the peer at some address was rejected,
+	/// because the connection was not authorized to use the handler with the provided id.
+	Denied(SocketAddr, String),
+	/// A stream from the peer at some address exceeded the [`limits`](crate::stream::Limits)
+	/// of the handler with the provided id (or of the connection, in which case the id is empty
+	/// because the limits of the connection are checked before the id is read),
+	/// and was queued, dropped, or reset according to the limits' [`overflow`](crate::stream::Overflow) policy.
+	Limited(SocketAddr, String),
+	/// A receiver for a stream from the peer at some address exceeded the [`timeout`](crate::stream::Registration::timeout)
+	/// of the handler with the provided id, so its streams were stopped.
+	TimedOut(SocketAddr, String),
+	/// The connection to the peer at some address was closed, by either side, by timing out, or by an error
+	/// (such as a transport error or a reset), which is reported exactly once per connection.
+	/// The peer's [`close code and reason`](Closed) are decoded so they can be shown to the user.
+	Closed(SocketAddr, Closed),
 }
 impl std::fmt::Debug for Event {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -17,6 +32,12 @@ impl std::fmt::Debug for Event {
 				connection.upgrade().unwrap().remote_address()
 			),
 			Self::Dropped(address) => write!(f, "Dropped({})", address),
+			Self::Denied(address, handler_id) => write!(f, "Denied({}, {})", address, handler_id),
+			Self::Limited(address, handler_id) => write!(f, "Limited({}, {})", address, handler_id),
+			Self::TimedOut(address, handler_id) => {
+				write!(f, "TimedOut({}, {})", address, handler_id)
+			}
+			Self::Closed(address, closed) => write!(f, "Closed({}, {})", address, closed),
 		}
 	}
 }
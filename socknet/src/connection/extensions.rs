@@ -0,0 +1,103 @@
+use std::{
+	any::{Any, TypeId},
+	collections::HashMap,
+	sync::RwLock,
+};
+
+type AnyBox = Box<dyn Any + Send + Sync + 'static>;
+
+/// Application state attached to a [`Connection`](super::Connection), keyed by its type.
+///
+/// Any handler can reach the extensions through its context's connection
+/// (for example, a login handler can insert the account of the peer for later handlers to use).
+/// At most one value of each type is stored, and all values are dropped with the connection.
+#[derive(Default)]
+pub struct Extensions(RwLock<HashMap<TypeId, AnyBox>>);
+
+impl Extensions {
+	/// Inserts a value, returning the value of the same type which it replaced (if any).
+	pub fn insert<T>(&self, value: T) -> Option<T>
+	where
+		T: Any + Send + Sync + 'static,
+	{
+		let previous = self
+			.0
+			.write()
+			.unwrap()
+			.insert(TypeId::of::<T>(), Box::new(value));
+		previous.and_then(|value| value.downcast::<T>().ok().map(|value| *value))
+	}
+
+	/// Removes the value of a type, returning it if there was one.
+	pub fn remove<T>(&self) -> Option<T>
+	where
+		T: Any + Send + Sync + 'static,
+	{
+		let removed = self.0.write().unwrap().remove(&TypeId::of::<T>());
+		removed.and_then(|value| value.downcast::<T>().ok().map(|value| *value))
+	}
+
+	/// Returns a copy of the value of a type.
+	pub fn get<T>(&self) -> Option<T>
+	where
+		T: Any + Send + Sync + Clone + 'static,
+	{
+		self.with(|value: &T| value.clone())
+	}
+
+	/// Returns a copy of the value of a type, first inserting the value returned by `f` if there is none.
+	///
+	/// The extensions are locked while `f` is called, so it must not access them itself.
+	pub fn get_or_insert_with<T, F>(&self, f: F) -> T
+	where
+		T: Any + Send + Sync + Clone + 'static,
+		F: FnOnce() -> T,
+	{
+		if let Some(value) = self.get::<T>() {
+			return value;
+		}
+		let mut values = self.0.write().unwrap();
+		let value = values
+			.entry(TypeId::of::<T>())
+			.or_insert_with(|| Box::new(f()));
+		value.downcast_ref::<T>().unwrap().clone()
+	}
+
+	/// Returns true if there is a value of the type.
+	pub fn contains<T>(&self) -> bool
+	where
+		T: Any + Send + Sync + 'static,
+	{
+		self.0.read().unwrap().contains_key(&TypeId::of::<T>())
+	}
+
+	/// Calls `f` with a reference to the value of a type (if there is one), returning its result.
+	///
+	/// The extensions are locked while `f` is called, so it must not access them itself.
+	pub fn with<T, F, R>(&self, f: F) -> Option<R>
+	where
+		T: Any + Send + Sync + 'static,
+		F: FnOnce(&T) -> R,
+	{
+		let values = self.0.read().unwrap();
+		values
+			.get(&TypeId::of::<T>())
+			.and_then(|value| value.downcast_ref::<T>())
+			.map(f)
+	}
+
+	/// Calls `f` with a mutable reference to the value of a type (if there is one), returning its result.
+	///
+	/// The extensions are locked while `f` is called, so it must not access them itself.
+	pub fn with_mut<T, F, R>(&self, f: F) -> Option<R>
+	where
+		T: Any + Send + Sync + 'static,
+		F: FnOnce(&mut T) -> R,
+	{
+		let mut values = self.0.write().unwrap();
+		values
+			.get_mut(&TypeId::of::<T>())
+			.and_then(|value| value.downcast_mut::<T>())
+			.map(f)
+	}
+}
@@ -0,0 +1,34 @@
+/// Settings which control the in-memory channels of [`local`](crate::connection::Active::is_local) connections.
+///
+/// Local connections are backed by bounded channels, so a fast local producer cannot grow memory without limit.
+/// Each capacity must be at least 1.
+#[derive(Clone, Debug)]
+pub struct LocalConfig {
+	/// The number of items which can be queued in a local stream before
+	/// [`writes`](crate::stream::kind::Write) to the stream wait for the reader to catch up.
+	///
+	/// Each value written is an item, or each chunk of bytes if
+	/// [`serialize_local`](crate::stream::Config::serialize_local) is enabled
+	/// (so every message takes 2 or more items).
+	pub stream_capacity: usize,
+	/// The number of datagrams which can be queued on a local connection before it is full.
+	/// Datagrams sent while the queue is full are dropped, like a remote datagram can be,
+	/// because datagrams are unreliable.
+	pub datagram_capacity: usize,
+}
+
+impl LocalConfig {
+	/// The default value of [`stream_capacity`](Self::stream_capacity).
+	pub const DEFAULT_STREAM_CAPACITY: usize = 64;
+	/// The default value of [`datagram_capacity`](Self::datagram_capacity).
+	pub const DEFAULT_DATAGRAM_CAPACITY: usize = 256;
+}
+
+impl Default for LocalConfig {
+	fn default() -> Self {
+		Self {
+			stream_capacity: Self::DEFAULT_STREAM_CAPACITY,
+			datagram_capacity: Self::DEFAULT_DATAGRAM_CAPACITY,
+		}
+	}
+}
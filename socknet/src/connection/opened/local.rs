@@ -1,5 +1,5 @@
 use crate::{
-	connection::{active, opened::Opened, Connection},
+	connection::{active, opened::Opened, Connection, LocalConfig},
 	endpoint::Endpoint,
 	stream::{
 		kind::{
@@ -19,21 +19,91 @@ pub struct Local {
 	incoming_datagrams: Incoming<Vec<AnyBox>>,
 }
 
+/// The receiving end of an [`active::Link`].
+pub(crate) struct Listeners {
+	pub(crate) uni_streams: Incoming<RecvLocalOngoing>,
+	pub(crate) bi_streams: Incoming<(SendLocalOngoing, RecvLocalOngoing)>,
+	pub(crate) datagrams: Incoming<Vec<AnyBox>>,
+}
+
+pub(crate) fn link(config: &LocalConfig) -> (active::Link, Listeners) {
+	let (uni_streams, incoming_uni_streams) = async_channel::unbounded();
+	let (bi_streams, incoming_bi_streams) = async_channel::unbounded();
+	let (datagrams, incoming_datagrams) = async_channel::bounded(config.datagram_capacity.max(1));
+	(
+		active::Link {
+			uni_streams,
+			bi_streams,
+			datagrams,
+		},
+		Listeners {
+			uni_streams: incoming_uni_streams,
+			bi_streams: incoming_bi_streams,
+			datagrams: incoming_datagrams,
+		},
+	)
+}
+
 impl Local {
-	pub fn new(endpoint: Weak<Endpoint>) -> Self {
-		let (outgoing_uni, incoming_uni_streams) = async_channel::unbounded();
-		let (outgoing_bi, incoming_bi_streams) = async_channel::unbounded();
-		let (outgoing_data, incoming_datagrams) = async_channel::unbounded();
+	/// Creates a connection from an endpoint to itself.
+	pub fn new(endpoint: &Arc<Endpoint>) -> Self {
+		let (link, listeners) = link(endpoint.local_config());
+		Self::side(
+			endpoint,
+			endpoint,
+			link.clone(),
+			link,
+			listeners,
+			Arc::new(tokio::sync::watch::channel(None).0),
+		)
+	}
+
+	/// Creates both sides of a connection between two endpoints in the same process,
+	/// returning the client's side and then the server's side.
+	pub fn pair(client: &Arc<Endpoint>, server: &Arc<Endpoint>) -> (Self, Self) {
+		let (to_server, server_listeners) = link(server.local_config());
+		let (to_client, client_listeners) = link(client.local_config());
+		let closed = Arc::new(tokio::sync::watch::channel(None).0);
+		(
+			Self::side(
+				client,
+				server,
+				to_server.clone(),
+				to_client.clone(),
+				client_listeners,
+				closed.clone(),
+			),
+			Self::side(
+				server,
+				client,
+				to_client,
+				to_server,
+				server_listeners,
+				closed,
+			),
+		)
+	}
+
+	fn side(
+		endpoint: &Arc<Endpoint>,
+		peer: &Arc<Endpoint>,
+		outgoing: active::Link,
+		incoming: active::Link,
+		listeners: Listeners,
+		closed: Arc<tokio::sync::watch::Sender<crate::stream::local::CloseState>>,
+	) -> Self {
 		Self {
 			active: active::Local {
-				endpoint,
-				uni_streams: outgoing_uni,
-				bi_streams: outgoing_bi,
-				datagrams: outgoing_data,
+				peer_address: peer.address(),
+				peer_certificate: peer.certificate().clone(),
+				outgoing,
+				incoming,
+				stream_capacity: endpoint.local_config().stream_capacity.max(1),
+				closed,
 			},
-			incoming_uni_streams,
-			incoming_bi_streams,
-			incoming_datagrams,
+			incoming_uni_streams: listeners.uni_streams,
+			incoming_bi_streams: listeners.bi_streams,
+			incoming_datagrams: listeners.datagrams,
 		}
 	}
 }
@@ -42,11 +112,7 @@ impl Opened for Local {
 	fn create(self, endpoint: Weak<Endpoint>) -> Weak<Connection> {
 		let handles = Arc::new(JoinHandleList::with_capacity(3));
 
-		let connection = Arc::new(Connection {
-			endpoint,
-			connection: Box::new(self.active),
-			handles,
-		});
+		let connection = Arc::new(Connection::new(endpoint, Box::new(self.active), handles));
 
 		connection
 			.clone()
@@ -17,11 +17,11 @@ impl Opened for Remote {
 	fn create(self, endpoint: Weak<Endpoint>) -> Weak<Connection> {
 		let handles = Arc::new(JoinHandleList::with_capacity(3));
 
-		let connection = Arc::new(Connection {
+		let connection = Arc::new(Connection::new(
 			endpoint,
-			connection: Box::new(active::Remote(self.0.connection)),
+			Box::new(active::Remote(self.0.connection)),
 			handles,
-		});
+		));
 
 		connection
 			.clone()
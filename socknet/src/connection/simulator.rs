@@ -0,0 +1,241 @@
+//! Simulated network conditions, for testing how an application copes with lag and loss
+//! without needing external tools.
+//!
+//! A [`Simulator`] is given to an [`Endpoint`](crate::endpoint::Endpoint) via
+//! [`set_simulator`](crate::endpoint::Endpoint::set_simulator), and applies its [`Conditions`]
+//! to all outgoing traffic of the connections the endpoint creates afterwards (remote and local).
+//! The conditions can be changed at any time, affecting connections which are already open.
+use crate::{
+	connection::{active::Active, CloseCode, Datagram},
+	stream::kind::{recv, send},
+	utility::PinFutureResultLifetime,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+	net::SocketAddr,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+use tokio::time::Instant;
+
+/// The network conditions applied by a [`Simulator`].
+///
+/// Streams are reliable and ordered, so only [`latency`](Self::latency), [`jitter`](Self::jitter)
+/// and [`bandwidth`](Self::bandwidth) apply to them: opening a stream is delayed,
+/// and so is every write to a stream (including the send half of bidirectional streams opened by the peer).
+/// A write only holds back the writer while it is sent at the bandwidth,
+/// and then arrives after the latency (never before the writes sent before it).
+/// All of the conditions apply to datagrams.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Conditions {
+	/// The delay added to all outgoing traffic.
+	pub latency: Duration,
+	/// The maximum random delay added on top of the [`latency`](Self::latency).
+	/// Datagrams with different delays can arrive out of order.
+	pub jitter: Duration,
+	/// The number of bytes which can be sent per second (shared by the datagrams and stream writes
+	/// of all connections using the simulator), if limited.
+	/// Datagrams and writes are queued (delayed) until the bandwidth is available.
+	/// Local datagrams and writes only count their bytes if they are serialized
+	/// (see [`serialize_local`](crate::stream::Config::serialize_local)).
+	pub bandwidth: Option<usize>,
+	/// The chance (0.0 to 1.0) that a datagram is lost.
+	pub loss: f64,
+	/// The chance (0.0 to 1.0) that a datagram is sent twice.
+	/// Local datagrams can only be duplicated if they are serialized
+	/// (see [`serialize_local`](crate::stream::Config::serialize_local)).
+	pub duplication: f64,
+	/// The chance (0.0 to 1.0) that a datagram is held back by the [`reorder_delay`](Self::reorder_delay),
+	/// so datagrams sent after it arrive first.
+	pub reordering: f64,
+	/// The delay added to datagrams which are reordered.
+	pub reorder_delay: Duration,
+}
+
+struct State {
+	conditions: Conditions,
+	rng: StdRng,
+	/// When all of the datagrams and writes sent so far have used their share of the bandwidth.
+	bandwidth_available_at: Instant,
+}
+
+/// Applies network [`Conditions`] to connections, with a seeded random generator
+/// so that the same traffic results in the same losses, duplicates, and delays.
+///
+/// Clones share the same conditions and random generator.
+#[derive(Clone)]
+pub struct Simulator(Arc<Mutex<State>>);
+
+impl Simulator {
+	pub fn new(seed: u64, conditions: Conditions) -> Self {
+		Self(Arc::new(Mutex::new(State {
+			conditions,
+			rng: StdRng::seed_from_u64(seed),
+			bandwidth_available_at: Instant::now(),
+		})))
+	}
+
+	pub fn conditions(&self) -> Conditions {
+		self.0.lock().unwrap().conditions.clone()
+	}
+
+	/// Changes the conditions, for all connections using the simulator.
+	pub fn set_conditions(&self, conditions: Conditions) {
+		self.0.lock().unwrap().conditions = conditions;
+	}
+
+	/// Wraps a connection, so that its outgoing traffic is subject to the simulated conditions.
+	pub(crate) fn wrap(
+		&self,
+		active: Box<dyn Active + Send + Sync + 'static>,
+	) -> Box<dyn Active + Send + Sync + 'static> {
+		Box::new(Simulated {
+			inner: Arc::from(active),
+			simulator: self.clone(),
+		})
+	}
+
+	fn delay(state: &mut State) -> Duration {
+		let jitter = state.conditions.jitter;
+		let jitter = match jitter.is_zero() {
+			true => Duration::ZERO,
+			false => state.rng.gen_range(Duration::ZERO..=jitter),
+		};
+		state.conditions.latency + jitter
+	}
+
+	/// Returns how long sending some bytes takes at the [`bandwidth`](Conditions::bandwidth),
+	/// including the time spent waiting for the bytes sent before them.
+	fn transmit(state: &mut State, size: usize, now: Instant) -> Duration {
+		match state.conditions.bandwidth {
+			Some(bandwidth) => {
+				let transmission = Duration::from_secs_f64(size as f64 / bandwidth.max(1) as f64);
+				state.bandwidth_available_at = state.bandwidth_available_at.max(now) + transmission;
+				state.bandwidth_available_at - now
+			}
+			None => Duration::ZERO,
+		}
+	}
+
+	/// Returns the delay before a stream is opened.
+	fn plan_stream(&self) -> Duration {
+		Self::delay(&mut self.0.lock().unwrap())
+	}
+
+	/// Makes every write to a stream subject to the conditions.
+	fn pace(&self, stream: &mut send::Ongoing) {
+		let (transmit, latency) = (self.clone(), self.clone());
+		stream.set_pace(send::ongoing::Pace {
+			transmit: Arc::new(move |size| {
+				Self::transmit(&mut transmit.0.lock().unwrap(), size, Instant::now())
+			}),
+			latency: Arc::new(move || Self::delay(&mut latency.0.lock().unwrap())),
+		});
+	}
+
+	/// Returns the delay of each copy of a datagram which is sent.
+	/// Empty if the datagram is lost.
+	fn plan_datagram(&self, size: usize) -> Vec<Duration> {
+		let mut guard = self.0.lock().unwrap();
+		let state = &mut *guard;
+		if state.rng.gen_bool(state.conditions.loss.clamp(0.0, 1.0)) {
+			return Vec::new();
+		}
+		let copies = match state
+			.rng
+			.gen_bool(state.conditions.duplication.clamp(0.0, 1.0))
+		{
+			true => 2,
+			false => 1,
+		};
+		let now = Instant::now();
+		(0..copies)
+			.map(|_| {
+				let mut delay = Self::delay(state) + Self::transmit(state, size, now);
+				if state
+					.rng
+					.gen_bool(state.conditions.reordering.clamp(0.0, 1.0))
+				{
+					delay += state.conditions.reorder_delay;
+				}
+				delay
+			})
+			.collect()
+	}
+}
+
+/// A connection whose outgoing traffic is subject to the conditions of a [`Simulator`].
+struct Simulated {
+	inner: Arc<dyn Active + Send + Sync + 'static>,
+	simulator: Simulator,
+}
+
+impl Simulated {
+	fn send_delayed(&self, datagram: Datagram, delay: Duration) -> anyhow::Result<()> {
+		if delay.is_zero() {
+			return self.inner.send_datagram(datagram);
+		}
+		let inner = self.inner.clone();
+		tokio::task::spawn(async move {
+			tokio::time::sleep(delay).await;
+			if let Err(error) = inner.send_datagram(datagram) {
+				log::trace!(target: crate::LOG, "Failed to send delayed datagram: {:?}", error);
+			}
+		});
+		Ok(())
+	}
+}
+
+impl Active for Simulated {
+	fn remote_address(&self) -> SocketAddr {
+		self.inner.remote_address()
+	}
+
+	fn peer_identity(&self) -> Option<Box<dyn std::any::Any>> {
+		self.inner.peer_identity()
+	}
+
+	fn is_local(&self) -> bool {
+		self.inner.is_local()
+	}
+
+	fn open_uni<'a>(&'a self) -> PinFutureResultLifetime<'a, send::Ongoing> {
+		Box::pin(async move {
+			tokio::time::sleep(self.simulator.plan_stream()).await;
+			let mut send = self.inner.open_uni().await?;
+			self.simulator.pace(&mut send);
+			Ok(send)
+		})
+	}
+
+	fn open_bi<'a>(&'a self) -> PinFutureResultLifetime<'a, (send::Ongoing, recv::Ongoing)> {
+		Box::pin(async move {
+			tokio::time::sleep(self.simulator.plan_stream()).await;
+			let (mut send, recv) = self.inner.open_bi().await?;
+			self.simulator.pace(&mut send);
+			Ok((send, recv))
+		})
+	}
+
+	fn send_datagram(&self, datagram: Datagram) -> anyhow::Result<()> {
+		let mut delays = self.simulator.plan_datagram(datagram.len());
+		let last = match delays.pop() {
+			Some(delay) => delay,
+			None => return Ok(()),
+		};
+		for delay in delays {
+			if let Some(copy) = datagram.try_clone() {
+				self.send_delayed(copy, delay)?;
+			}
+		}
+		self.send_delayed(datagram, last)
+	}
+
+	fn accept_send(&self, stream: &mut send::Ongoing) {
+		self.simulator.pace(stream);
+	}
+
+	fn close(&self, code: CloseCode, reason: &str) {
+		self.inner.close(code, reason);
+	}
+}
@@ -1,6 +1,6 @@
 use crate::{
 	connection::{self, Connection},
-	stream::Registry,
+	stream::{self, Registry},
 	utility::JoinHandleList,
 };
 use std::{
@@ -25,7 +25,31 @@ pub struct ClientConfig {
 	pub private_key: rustls::PrivateKey,
 }
 
+/// The side of connections which an endpoint is (determined by its [`Config`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+	Server,
+	Client,
+}
+
+impl Role {
+	/// The role of the other side of a connection.
+	pub fn peer(self) -> Self {
+		match self {
+			Self::Server => Self::Client,
+			Self::Client => Self::Server,
+		}
+	}
+}
+
 impl Config {
+	pub fn role(&self) -> Role {
+		match &self {
+			Self::Server(_) => Role::Server,
+			Self::Client(_) => Role::Client,
+		}
+	}
+
 	pub fn fingerprint(&self) -> String {
 		use crate::utility::fingerprint;
 		match &self {
@@ -37,18 +61,25 @@ impl Config {
 
 pub struct Endpoint {
 	endpoint: Arc<quinn::Endpoint>,
+	role: Role,
 	certificate: rustls::Certificate,
 	private_key: rustls::PrivateKey,
 	handles: JoinHandleList,
 	pub(crate) connection_sender: connection::event::Sender,
 	connection_receiver: connection::event::Receiver,
 	pub(crate) stream_registry: Arc<Registry>,
+	stream_config: stream::Config,
+	local_config: connection::LocalConfig,
+	#[cfg(feature = "simulator")]
+	simulator: std::sync::RwLock<Option<connection::simulator::Simulator>>,
+	recorder: std::sync::RwLock<Option<stream::capture::Recorder>>,
 }
 
 impl Drop for Endpoint {
 	fn drop(&mut self) {
 		log::info!(target: crate::LOG, "Closing endpoint {}", self.address());
-		self.endpoint.close(quinn::VarInt::from_u32(0), &vec![]);
+		self.endpoint
+			.close(connection::CloseCode::Shutdown.into(), &[]);
 	}
 }
 
@@ -59,20 +90,29 @@ impl Endpoint {
 
 	pub(crate) fn new(
 		endpoint: quinn::Endpoint,
+		role: Role,
 		certificate: rustls::Certificate,
 		private_key: rustls::PrivateKey,
 		stream_registry: Arc<Registry>,
+		stream_config: stream::Config,
+		local_config: connection::LocalConfig,
 	) -> Self {
 		let endpoint = Arc::new(endpoint);
 		let (connection_sender, connection_receiver) = async_channel::unbounded();
 		Self {
 			endpoint,
+			role,
 			certificate,
 			private_key,
 			handles: JoinHandleList::new(),
 			connection_sender,
 			connection_receiver,
 			stream_registry,
+			stream_config,
+			local_config,
+			#[cfg(feature = "simulator")]
+			simulator: std::sync::RwLock::new(None),
+			recorder: std::sync::RwLock::new(None),
 		}
 	}
 
@@ -96,6 +136,11 @@ impl Endpoint {
 		}));
 	}
 
+	/// Whether the endpoint was configured as a server or a client.
+	pub fn role(&self) -> Role {
+		self.role
+	}
+
 	pub fn connection_receiver(&self) -> &connection::event::Receiver {
 		&self.connection_receiver
 	}
@@ -111,6 +156,46 @@ impl Endpoint {
 	pub fn fingerprint(&self) -> String {
 		crate::utility::fingerprint(&self.certificate)
 	}
+
+	/// The registry of handlers which the connections of this endpoint use by default
+	/// (see [`Connection::registry`]).
+	pub fn stream_registry(&self) -> &Arc<Registry> {
+		&self.stream_registry
+	}
+
+	/// The base [`configuration`](stream::Config) for all streams opened or received by this endpoint.
+	pub fn stream_config(&self) -> &stream::Config {
+		&self.stream_config
+	}
+
+	/// The channel capacities of [`local`](connection::Active::is_local) connections made by this endpoint.
+	pub fn local_config(&self) -> &connection::LocalConfig {
+		&self.local_config
+	}
+
+	/// Simulates network conditions on all connections created by this endpoint after this call.
+	/// Connections which are already open keep the simulator they were created with (if any).
+	#[cfg(feature = "simulator")]
+	pub fn set_simulator(&self, simulator: Option<connection::simulator::Simulator>) {
+		*self.simulator.write().unwrap() = simulator;
+	}
+
+	/// The simulator applied to new connections, if any.
+	#[cfg(feature = "simulator")]
+	pub fn simulator(&self) -> Option<connection::simulator::Simulator> {
+		self.simulator.read().unwrap().clone()
+	}
+
+	/// Records the traffic of all streams opened or received by this endpoint after this call.
+	/// Streams which are already open are not recorded.
+	pub fn set_recorder(&self, recorder: Option<stream::capture::Recorder>) {
+		*self.recorder.write().unwrap() = recorder;
+	}
+
+	/// The recorder of new streams, if any.
+	pub fn recorder(&self) -> Option<stream::capture::Recorder> {
+		self.recorder.read().unwrap().clone()
+	}
 }
 
 impl Endpoint {
@@ -156,11 +241,29 @@ impl Endpoint {
 			}
 			true => {
 				use connection::opened::Local;
-				Connection::create(&self, Local::new(Arc::downgrade(&self)))
+				Connection::create(&self, Local::new(self))
 			}
 		})
 	}
 
+	/// Connects to another endpoint in the same process, without sending any data over a socket.
+	///
+	/// Each endpoint gets its own view of the connection (and a [`Created`](connection::event::Event::Created) event for it),
+	/// in which the [`remote address`](connection::Active::remote_address) and identity are those of the other endpoint.
+	/// This endpoint's view is returned, and the other endpoint acts as the server.
+	/// Both endpoints must agree on their [`stream configs`](Self::stream_config), like they would for a remote connection.
+	pub fn connect_in_memory(self: &Arc<Self>, server: &Arc<Endpoint>) -> Weak<Connection> {
+		use connection::opened::Local;
+		log::info!(
+			target: crate::LOG,
+			"Connecting in memory to {}",
+			server.address()
+		);
+		let (client_side, server_side) = Local::pair(self, server);
+		Connection::create(server, server_side);
+		Connection::create(self, client_side)
+	}
+
 	pub(crate) fn send_connection_event(&self, event: connection::event::Event) {
 		use async_channel::TrySendError;
 		let log_target = self.log_target();
@@ -10,3 +10,6 @@ pub mod connection;
 pub mod endpoint;
 pub mod stream;
 pub mod utility;
+
+#[cfg(feature = "testing")]
+pub mod testing;
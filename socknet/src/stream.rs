@@ -3,7 +3,20 @@ use std::sync::Arc;
 
 /// Contains structures for the different forms of data streams.
 pub mod kind;
-pub(crate) mod local;
+/// Types for the values sent over local (same-application) connections.
+pub mod local;
+
+pub mod capture;
+/// Codecs used to convert values to and from the bytes sent over streams.
+pub mod codec;
+/// Optional compression of the messages sent over streams.
+pub mod compression;
+/// Encoding of the size headers which prefix messages sent over streams.
+pub mod framing;
+
+#[doc(hidden)]
+mod config;
+pub use config::*;
 
 /// API interface for creating builders for [`Receivers`](handler::Receiver) and their contexts.
 pub mod recv;
@@ -23,6 +36,18 @@ pub mod handler;
 mod registry;
 pub use registry::*;
 
+#[doc(hidden)]
+mod interceptor;
+pub use interceptor::*;
+
+#[doc(hidden)]
+mod limits;
+pub use limits::*;
+
+#[doc(hidden)]
+mod timeout;
+pub use timeout::*;
+
 /// Identifies a stream handler.
 /// Used to write the id to an outgoing/send stream so when the
 /// incoming/receiving end finds the stream, it knows what handler to build to respond/react.
@@ -73,18 +98,54 @@ pub trait Identifier {
 		let builder = self.send_builder().clone();
 		Box::pin(async move {
 			use send::AppContext;
-			let mut stream = Self::SendBuilder::open(connection.clone()).await?;
-			// Because the stream is identified, we should always write the id of the stream when its opened.
-			// If a user is not using the built-in identifier system, they shouldn't be using this trait.
-			{
-				use kind::send::Write;
-				stream.write(&Self::unique_id().to_owned()).await?;
+			let kind = <<Self::SendBuilder as send::AppContext>::Opener as Opener>::kind();
+			let completion = Registry::intercept_open(Self::unique_id(), &connection, kind)?;
+			let opened: anyhow::Result<send::Context<Self::SendBuilder>> = async move {
+				let mut stream = Self::SendBuilder::open(connection.clone()).await?;
+				let mut endpoint_config = connection.stream_config()?;
+				let recorder = connection
+					.endpoint()
+					.ok()
+					.and_then(|endpoint| endpoint.recorder());
+				if let Some(recorder) = recorder {
+					use crate::connection::Active;
+					let tap =
+						recorder.tap(kind, capture::Initiator::Local, connection.remote_address());
+					tap.record(capture::Event::Identified(Self::unique_id().to_owned()));
+					endpoint_config.capture = Some(tap);
+				}
+				{
+					use kind::Configure;
+					stream.configure(&endpoint_config);
+				}
+				// Because the stream is identified, we should always write the id of the stream when its opened.
+				// If a user is not using the built-in identifier system, they shouldn't be using this trait.
+				{
+					use kind::send::Write;
+					stream
+						.write_with(&codec::Bincode, &Self::unique_id().to_owned())
+						.await?;
+				}
+				// Only once the id has been written can the stream be configured for the handler.
+				{
+					use kind::Configure;
+					let mut config = endpoint_config;
+					builder.configure(&mut config);
+					stream.configure(&config);
+				}
+				Ok(send::Context {
+					builder,
+					connection,
+					stream,
+					permit: Permit::default(),
+				})
+			}
+			.await;
+			match &opened {
+				Ok(_) => completion.complete(&Outcome::Opened),
+				Err(error) => completion.complete(&Outcome::Failed(error)),
 			}
-			Ok(send::Context {
-				builder,
-				connection,
-				stream,
-			})
+			opened
 		})
 	}
 }
@@ -97,6 +158,9 @@ pub struct Context<T, S> {
 	pub connection: Arc<Connection>,
 	/// The stream(s) for the context's connections that the handler needs to process.
 	pub stream: S,
+	/// Keeps the handler's receiver active for the [`limits`](Limits) of the stream, until the context is dropped.
+	/// Outgoing streams are not limited, so their permit is empty.
+	pub permit: Permit,
 }
 
 /// Trait implemented to specialize how different streams are opened.
@@ -108,6 +172,8 @@ pub struct Context<T, S> {
 pub trait Opener {
 	type Output;
 	fn open(connection: Arc<Connection>) -> PinFutureResult<Self::Output>;
+	/// The kind of stream which is opened, as it is [`recorded`](capture::Recorder).
+	fn kind() -> capture::Kind;
 }
 
 /// Trait implemented to specialize how different streams are extracted from the [`Kind`](kind::Kind) enum.
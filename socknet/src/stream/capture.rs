@@ -0,0 +1,291 @@
+//! Recording the traffic of stream handlers, and replaying it to reproduce bugs.
+//!
+//! A [`Recorder`] is given to an [`Endpoint`] via [`set_recorder`](Endpoint::set_recorder).
+//! Every stream opened or received by the endpoint afterwards is assigned an id in the capture,
+//! and the recorder logs when the stream was opened (and for which handler),
+//! followed by every framed message sent or received on it.
+//! The framed messages are exactly the bytes on the wire (after compression),
+//! so streams of local connections are only recorded if
+//! [`serialize_local`](crate::stream::Config::serialize_local) is enabled.
+//!
+//! A capture can be [`read`] back, and the streams which the peer opened can be
+//! [`replayed`](replay) into the registry of an endpoint, as if the peer had sent them again.
+use crate::{
+	connection::{opened, Connection},
+	endpoint::Endpoint,
+	stream::{
+		framing::Framing,
+		kind::{self, recv, send},
+		local, Registry,
+	},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	net::SocketAddr,
+	path::Path,
+	sync::{Arc, Mutex, Weak},
+	time::{Duration, Instant},
+};
+
+/// The kinds of streams which can be recorded.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+	Unidirectional,
+	Bidirectional,
+	Datagram,
+}
+
+impl From<&kind::Kind> for Kind {
+	fn from(stream: &kind::Kind) -> Self {
+		match stream {
+			kind::Kind::Unidirectional(_) => Self::Unidirectional,
+			kind::Kind::Bidirectional(_) => Self::Bidirectional,
+			kind::Kind::Datagram(_) => Self::Datagram,
+		}
+	}
+}
+
+/// Which end of the connection opened a stream.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Initiator {
+	/// The stream was opened by the recording endpoint.
+	Local,
+	/// The stream was opened by the peer, and received by the recording endpoint.
+	Peer,
+}
+
+/// Something which happened to a recorded stream.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Event {
+	/// The stream was opened (the first event of every stream).
+	Opened {
+		kind: Kind,
+		initiator: Initiator,
+		peer: SocketAddr,
+	},
+	/// The id of the handler for the stream was written or read.
+	Identified(String),
+	/// A framed message was written to the stream.
+	Sent(Vec<u8>),
+	/// A framed message was read from the stream.
+	Received(Vec<u8>),
+}
+
+/// An entry in a capture.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Record {
+	/// The id of the stream in the capture (unique per recorder).
+	pub stream: u64,
+	/// The time since the recorder was created.
+	pub time: Duration,
+	pub event: Event,
+}
+
+struct State {
+	output: Box<dyn std::io::Write + Send>,
+	start: Instant,
+	next_stream: u64,
+}
+
+/// Writes [`Records`](Record) to some output, like a capture file.
+///
+/// Each record is encoded with [`bincode`], and prefixed with a [`varint`](Framing::Varint) size header.
+/// Clones share the same output.
+#[derive(Clone)]
+pub struct Recorder(Arc<Mutex<State>>);
+
+impl Recorder {
+	pub fn new<W>(output: W) -> Self
+	where
+		W: std::io::Write + Send + 'static,
+	{
+		Self(Arc::new(Mutex::new(State {
+			output: Box::new(output),
+			start: Instant::now(),
+			next_stream: 0,
+		})))
+	}
+
+	/// Creates a recorder which writes to a (new or truncated) capture file.
+	pub fn create<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+		let file = std::fs::File::create(path)?;
+		Ok(Self::new(std::io::BufWriter::new(file)))
+	}
+
+	/// Writes any buffered records to the output.
+	pub fn flush(&self) -> std::io::Result<()> {
+		self.0.lock().unwrap().output.flush()
+	}
+
+	/// Starts recording a new stream.
+	pub(crate) fn tap(&self, kind: Kind, initiator: Initiator, peer: SocketAddr) -> Tap {
+		let stream = {
+			let mut state = self.0.lock().unwrap();
+			state.next_stream += 1;
+			state.next_stream
+		};
+		let tap = Tap {
+			recorder: self.clone(),
+			stream,
+		};
+		tap.record(Event::Opened {
+			kind,
+			initiator,
+			peer,
+		});
+		tap
+	}
+
+	fn record(&self, stream: u64, event: Event) {
+		let mut state = self.0.lock().unwrap();
+		let record = Record {
+			stream,
+			time: state.start.elapsed(),
+			event,
+		};
+		let result = bincode::serialize(&record)
+			.map_err(anyhow::Error::from)
+			.and_then(|bytes| {
+				state
+					.output
+					.write_all(&Framing::Varint.encode(bytes.len())?)?;
+				state.output.write_all(&bytes)?;
+				Ok(())
+			});
+		if let Err(error) = result {
+			log::error!(target: crate::LOG, "Failed to write capture record: {:?}", error);
+		}
+	}
+}
+
+/// Records the events of a single stream.
+///
+/// Provided to a stream via its [`config`](crate::stream::Config::capture)
+/// when the endpoint has a [`Recorder`].
+#[derive(Clone)]
+pub struct Tap {
+	recorder: Recorder,
+	stream: u64,
+}
+
+impl std::fmt::Debug for Tap {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "Tap({})", self.stream)
+	}
+}
+
+impl Tap {
+	pub(crate) fn record(&self, event: Event) {
+		self.recorder.record(self.stream, event);
+	}
+}
+
+/// Reads all of the records in a capture.
+///
+/// Fails if the capture ends part way through a record.
+pub fn read<R: std::io::Read>(mut input: R) -> anyhow::Result<Vec<Record>> {
+	use std::io::Read;
+	let mut records = Vec::new();
+	loop {
+		let mut header = vec![0u8; 1];
+		match input.read_exact(&mut header) {
+			Ok(()) => {}
+			Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => break,
+			Err(error) => return Err(error)?,
+		}
+		header.resize(Framing::Varint.header_len(header[0]), 0);
+		input.read_exact(&mut header[1..])?;
+		// The size is only trusted as far as the input has the bytes,
+		// so a corrupt size header can not allocate more than the rest of the capture.
+		let size = Framing::Varint.decode(&header)?;
+		let mut bytes = Vec::new();
+		(&mut input).take(size as u64).read_to_end(&mut bytes)?;
+		if bytes.len() != size {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::UnexpectedEof,
+				format!(
+					"capture record of {} bytes is truncated to {} bytes",
+					size,
+					bytes.len()
+				),
+			)
+			.into());
+		}
+		records.push(bincode::deserialize(&bytes)?);
+	}
+	Ok(records)
+}
+
+/// Reads all of the records in a capture file.
+pub fn read_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<Record>> {
+	read(std::io::BufReader::new(std::fs::File::open(path)?))
+}
+
+/// Feeds the streams which the peer opened in a capture into the registry of an endpoint,
+/// in the order they were opened, as if the peer had sent them again.
+///
+/// The streams are received on a synthetic local connection (from the endpoint to itself), which is returned.
+/// Anything the endpoint's handlers write back to the peer is discarded.
+pub fn replay(endpoint: &Arc<Endpoint>, records: &[Record]) -> Weak<Connection> {
+	let connection = Connection::create(endpoint, opened::Local::new(endpoint));
+	let connection = match connection.upgrade() {
+		Some(connection) => connection,
+		None => return Weak::new(),
+	};
+
+	let mut order = Vec::new();
+	let mut streams: HashMap<u64, (Kind, Vec<Vec<u8>>)> = HashMap::new();
+	for record in records {
+		match &record.event {
+			Event::Opened {
+				kind,
+				initiator: Initiator::Peer,
+				..
+			} => {
+				order.push(record.stream);
+				streams.insert(record.stream, (*kind, Vec::new()));
+			}
+			Event::Received(bytes) => {
+				if let Some((_, messages)) = streams.get_mut(&record.stream) {
+					messages.push(bytes.clone());
+				}
+			}
+			_ => {}
+		}
+	}
+
+	for id in order {
+		let (kind, messages) = match streams.remove(&id) {
+			Some(stream) => stream,
+			None => continue,
+		};
+		let stream = match kind {
+			Kind::Datagram => kind::Kind::from(bytes::Bytes::from(messages.concat())),
+			Kind::Unidirectional => kind::Kind::Unidirectional(replay_recv(messages)),
+			Kind::Bidirectional => {
+				let (sink, discarded) = async_channel::unbounded::<local::AnyBox>();
+				tokio::task::spawn(async move { while discarded.recv().await.is_ok() {} });
+				let send =
+					send::ongoing::local::Internal(sink, never_closed(), local::Codes::default());
+				kind::Kind::Bidirectional((send.into(), replay_recv(messages)))
+			}
+		};
+		Registry::create_receiver(connection.clone(), stream);
+	}
+
+	Arc::downgrade(&connection)
+}
+
+/// A recv stream which yields the captured messages, and then ends.
+fn replay_recv(messages: Vec<Vec<u8>>) -> recv::Ongoing {
+	let (sender, receiver) = async_channel::unbounded::<local::AnyBox>();
+	for message in messages {
+		let _ = sender.try_send(Box::new(message));
+	}
+	recv::Ongoing::Local(recv::ongoing::Local::serialized(receiver, never_closed()))
+}
+
+fn never_closed() -> local::Closed {
+	tokio::sync::watch::channel(None).1.into()
+}
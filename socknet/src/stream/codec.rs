@@ -0,0 +1,169 @@
+/// Converts values to and from the bytes which are transmitted over a stream.
+///
+/// Implementations can be used directly via [`write_with`](crate::stream::kind::Write::write_with)
+/// and [`read_with`](crate::stream::kind::Read::read_with), or selected at runtime
+/// for a handler or endpoint via the [`Format`] in a [`stream config`](crate::stream::Config).
+pub trait Codec {
+	/// Serializes a value into a buffer of bytes.
+	fn encode<T>(&self, value: &T) -> anyhow::Result<Vec<u8>>
+	where
+		T: serde::Serialize;
+
+	/// Deserializes a value from a buffer of bytes.
+	fn decode<T>(&self, bytes: &[u8]) -> anyhow::Result<T>
+	where
+		T: serde::de::DeserializeOwned;
+}
+
+/// The [`bincode`] codec. This is the default format, and is always used to encode handler ids.
+pub struct Bincode;
+impl Codec for Bincode {
+	fn encode<T>(&self, value: &T) -> anyhow::Result<Vec<u8>>
+	where
+		T: serde::Serialize,
+	{
+		Ok(bincode::serialize(value)?)
+	}
+
+	fn decode<T>(&self, bytes: &[u8]) -> anyhow::Result<T>
+	where
+		T: serde::de::DeserializeOwned,
+	{
+		Ok(bincode::deserialize(bytes)?)
+	}
+}
+
+/// The JSON codec, provided by [`serde_json`].
+#[cfg(feature = "codec-json")]
+pub struct Json;
+#[cfg(feature = "codec-json")]
+impl Codec for Json {
+	fn encode<T>(&self, value: &T) -> anyhow::Result<Vec<u8>>
+	where
+		T: serde::Serialize,
+	{
+		Ok(serde_json::to_vec(value)?)
+	}
+
+	fn decode<T>(&self, bytes: &[u8]) -> anyhow::Result<T>
+	where
+		T: serde::de::DeserializeOwned,
+	{
+		Ok(serde_json::from_slice(bytes)?)
+	}
+}
+
+/// The MessagePack codec, provided by [`rmp_serde`].
+/// Structs are encoded as maps, so peers do not need to know the field order.
+#[cfg(feature = "codec-msgpack")]
+pub struct MessagePack;
+#[cfg(feature = "codec-msgpack")]
+impl Codec for MessagePack {
+	fn encode<T>(&self, value: &T) -> anyhow::Result<Vec<u8>>
+	where
+		T: serde::Serialize,
+	{
+		Ok(rmp_serde::to_vec_named(value)?)
+	}
+
+	fn decode<T>(&self, bytes: &[u8]) -> anyhow::Result<T>
+	where
+		T: serde::de::DeserializeOwned,
+	{
+		Ok(rmp_serde::from_slice(bytes)?)
+	}
+}
+
+/// The postcard codec, provided by [`postcard`].
+#[cfg(feature = "codec-postcard")]
+pub struct Postcard;
+#[cfg(feature = "codec-postcard")]
+impl Codec for Postcard {
+	fn encode<T>(&self, value: &T) -> anyhow::Result<Vec<u8>>
+	where
+		T: serde::Serialize,
+	{
+		Ok(postcard::to_allocvec(value)?)
+	}
+
+	fn decode<T>(&self, bytes: &[u8]) -> anyhow::Result<T>
+	where
+		T: serde::de::DeserializeOwned,
+	{
+		Ok(postcard::from_bytes(bytes)?)
+	}
+}
+
+/// Runtime selection of one of the built-in [`codecs`](Codec).
+///
+/// Both peers must use the same format for a given handler,
+/// otherwise reading values from the stream will fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+	/// Uses [`Bincode`], which is compact and fast but only readable by other Rust peers.
+	Bincode,
+	/// Uses [`Json`], which is human-readable and supported by peers in any language.
+	#[cfg(feature = "codec-json")]
+	Json,
+	/// Uses [`MessagePack`], a binary format which is supported by peers in most languages.
+	#[cfg(feature = "codec-msgpack")]
+	MessagePack,
+	/// Uses [`Postcard`], a very compact format suited to embedded peers.
+	#[cfg(feature = "codec-postcard")]
+	Postcard,
+}
+
+// Deriving needs `#[default]` on the variant, which would raise the minimum supported Rust version to 1.62.
+#[allow(clippy::derivable_impls)]
+impl Default for Format {
+	fn default() -> Self {
+		Self::Bincode
+	}
+}
+
+impl Format {
+	/// Returns every format which is enabled by the crate's features.
+	pub fn all() -> Vec<Self> {
+		vec![
+			Self::Bincode,
+			#[cfg(feature = "codec-json")]
+			Self::Json,
+			#[cfg(feature = "codec-msgpack")]
+			Self::MessagePack,
+			#[cfg(feature = "codec-postcard")]
+			Self::Postcard,
+		]
+	}
+}
+
+impl Codec for Format {
+	fn encode<T>(&self, value: &T) -> anyhow::Result<Vec<u8>>
+	where
+		T: serde::Serialize,
+	{
+		match self {
+			Self::Bincode => Bincode.encode(value),
+			#[cfg(feature = "codec-json")]
+			Self::Json => Json.encode(value),
+			#[cfg(feature = "codec-msgpack")]
+			Self::MessagePack => MessagePack.encode(value),
+			#[cfg(feature = "codec-postcard")]
+			Self::Postcard => Postcard.encode(value),
+		}
+	}
+
+	fn decode<T>(&self, bytes: &[u8]) -> anyhow::Result<T>
+	where
+		T: serde::de::DeserializeOwned,
+	{
+		match self {
+			Self::Bincode => Bincode.decode(bytes),
+			#[cfg(feature = "codec-json")]
+			Self::Json => Json.decode(bytes),
+			#[cfg(feature = "codec-msgpack")]
+			Self::MessagePack => MessagePack.decode(bytes),
+			#[cfg(feature = "codec-postcard")]
+			Self::Postcard => Postcard.decode(bytes),
+		}
+	}
+}
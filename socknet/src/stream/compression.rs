@@ -0,0 +1,118 @@
+/// The header byte written before a message which was sent without compression.
+const UNCOMPRESSED: u8 = 0;
+/// The header byte written before a message which was compressed with [`zstd`](Algorithm::Zstd).
+#[cfg(feature = "compression-zstd")]
+const ZSTD: u8 = 1;
+/// The header byte written before a message which was compressed with [`lz4`](Algorithm::Lz4).
+#[cfg(feature = "compression-lz4")]
+const LZ4: u8 = 2;
+
+/// The algorithms which can be used to compress messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+	/// Compression provided by [`zstd`], at some compression level (1-22, where 0 is the zstd default).
+	#[cfg(feature = "compression-zstd")]
+	Zstd { level: i32 },
+	/// Compression provided by [`lz4_flex`].
+	#[cfg(feature = "compression-lz4")]
+	Lz4,
+}
+
+/// Per-message compression for the [`bytes`](crate::stream::kind::Write::write_bytes) sent over a stream.
+///
+/// When enabled in a [`stream config`](crate::stream::Config), every message is prefixed with a header byte
+/// which tells the reader if (and how) the message was compressed. Messages smaller than the
+/// [`threshold`](Self::threshold) are sent uncompressed, as compressing them rarely saves any bytes.
+///
+/// Local streams never compress their messages (even if [`serialize_local`](crate::stream::Config::serialize_local) is enabled),
+/// because the data never leaves the process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Compression {
+	/// The algorithm used to compress messages.
+	pub algorithm: Algorithm,
+	/// The minimum size, in bytes, of a message to compress.
+	pub threshold: usize,
+}
+
+impl Compression {
+	/// The threshold used by [`new`](Self::new).
+	pub const DEFAULT_THRESHOLD: usize = 256;
+
+	pub fn new(algorithm: Algorithm) -> Self {
+		Self {
+			algorithm,
+			threshold: Self::DEFAULT_THRESHOLD,
+		}
+	}
+
+	/// Prefixes the message with its compression header, compressing the message if it is large enough.
+	pub(crate) fn encode(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+		if data.len() < self.threshold {
+			return Ok(Self::frame(UNCOMPRESSED, data));
+		}
+		match self.algorithm {
+			#[cfg(feature = "compression-zstd")]
+			Algorithm::Zstd { level } => Ok(Self::frame(ZSTD, &zstd::bulk::compress(data, level)?)),
+			#[cfg(feature = "compression-lz4")]
+			Algorithm::Lz4 => Ok(Self::frame(LZ4, &lz4_flex::compress_prepend_size(data))),
+		}
+	}
+
+	fn frame(header: u8, message: &[u8]) -> Vec<u8> {
+		let mut frame = Vec::with_capacity(message.len() + 1);
+		frame.push(header);
+		frame.extend_from_slice(message);
+		frame
+	}
+
+	/// Reads the compression header of a message, decompressing the message if needed.
+	/// Fails if the decompressed message would be larger than `limit`.
+	///
+	/// Messages compressed with any of the algorithms enabled by the crate's features can be decoded,
+	/// regardless of the algorithm used to send messages.
+	#[cfg_attr(
+		not(any(feature = "compression-zstd", feature = "compression-lz4")),
+		allow(unused_variables)
+	)]
+	pub(crate) fn decode(&self, frame: Vec<u8>, limit: usize) -> anyhow::Result<Vec<u8>> {
+		let (header, message) = frame.split_first().ok_or(Error::MissingHeader)?;
+		match *header {
+			UNCOMPRESSED => Ok(message.to_vec()),
+			#[cfg(feature = "compression-zstd")]
+			ZSTD => {
+				use crate::stream::kind::recv::Error::MessageTooLarge;
+				use std::io::Read;
+				// Decode at most 1 byte more than the limit, to detect (without allocating) oversized messages.
+				let mut decoded = Vec::new();
+				zstd::stream::read::Decoder::new(message)?
+					.take(limit as u64 + 1)
+					.read_to_end(&mut decoded)?;
+				if decoded.len() > limit {
+					return Err(MessageTooLarge {
+						size: decoded.len(),
+						limit,
+					})?;
+				}
+				Ok(decoded)
+			}
+			#[cfg(feature = "compression-lz4")]
+			LZ4 => {
+				use crate::stream::kind::recv::Error::MessageTooLarge;
+				let (size, compressed) = lz4_flex::block::uncompressed_size(message)?;
+				if size > limit {
+					return Err(MessageTooLarge { size, limit })?;
+				}
+				Ok(lz4_flex::block::decompress(compressed, size)?)
+			}
+			unknown => Err(Error::UnsupportedHeader(unknown))?,
+		}
+	}
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+	#[error("Compressed message is missing its header byte.")]
+	MissingHeader,
+	#[error("Message was compressed with an unknown or disabled algorithm (header {0}).")]
+	UnsupportedHeader(u8),
+}
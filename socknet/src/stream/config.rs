@@ -0,0 +1,65 @@
+use crate::stream::{capture, codec, compression::Compression, framing::Framing, Deadline};
+
+/// Settings which control how data is written to and read from streams.
+///
+/// Each [`endpoint`](crate::endpoint::Endpoint) has a base configuration (provided by [`crate::Config`]),
+/// which each handler can adjust for its own streams via
+/// [`send::AppContext::configure`](crate::stream::send::AppContext::configure) and
+/// [`recv::AppContext::configure`](crate::stream::recv::AppContext::configure).
+/// The initiating and receiving ends of a handler must agree on the configuration.
+#[derive(Clone, Debug)]
+pub struct Config {
+	/// The codec used by [`write`](crate::stream::kind::Write::write)
+	/// and [`read`](crate::stream::kind::Read::read) to encode values.
+	pub codec: codec::Format,
+	/// If provided, messages written by [`write_bytes`](crate::stream::kind::Write::write_bytes)
+	/// are compressed when they are larger than the compression threshold.
+	pub compression: Option<Compression>,
+	/// The largest message, in bytes, which a peer is allowed to send.
+	/// Applies to both the size header of a message and its decompressed size.
+	/// Messages which exceed the limit are rejected before any buffer is allocated for them.
+	pub max_message_size: usize,
+	/// The largest handler id, in bytes, which a peer is allowed to send when opening a stream.
+	/// Because the handler id is read before the handler is known,
+	/// only the value configured for the endpoint is used.
+	pub max_handler_id_size: usize,
+	/// The encoding of the size header written before each message.
+	/// Like the handler id size, only the value configured for the endpoint is used,
+	/// because the framing must be known in order to read the handler id.
+	pub framing: Framing,
+	/// If enabled, streams over [`local`](crate::connection::Active::is_local) connections serialize
+	/// every write into bytes (using the codec, compression, and framing of the stream)
+	/// and deserialize them on read, exactly like remote streams do.
+	/// Otherwise local streams pass values through as-is, which is faster but skips the wire format entirely.
+	///
+	/// Useful for tests and debug builds, so that serialization issues surface without needing a socket.
+	pub serialize_local: bool,
+	/// Records the messages of the stream, if the endpoint has a [`Recorder`](capture::Recorder).
+	/// This is managed by the endpoint, and is kept regardless of how a handler configures its streams.
+	pub capture: Option<capture::Tap>,
+	/// Bounds the reads and writes of the stream, if its handler has a [`timeout`](crate::stream::Registration::timeout).
+	/// Like the capture, this is managed by the registry, and is kept regardless of how a handler configures its streams.
+	pub deadline: Option<Deadline>,
+}
+
+impl Config {
+	/// The default value of [`max_message_size`](Self::max_message_size), 16 MiB.
+	pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+	/// The default value of [`max_handler_id_size`](Self::max_handler_id_size).
+	pub const DEFAULT_MAX_HANDLER_ID_SIZE: usize = 256;
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			codec: codec::Format::default(),
+			compression: None,
+			max_message_size: Self::DEFAULT_MAX_MESSAGE_SIZE,
+			max_handler_id_size: Self::DEFAULT_MAX_HANDLER_ID_SIZE,
+			framing: Framing::default(),
+			serialize_local: false,
+			capture: None,
+			deadline: None,
+		}
+	}
+}
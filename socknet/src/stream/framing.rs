@@ -0,0 +1,95 @@
+use std::convert::TryFrom;
+
+/// The encoding used for the size headers which prefix each message written to a stream.
+///
+/// Both peers must use the same framing, so this should be configured per endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+	/// QUIC-style variable-length integers (RFC 9000, section 16),
+	/// which take 1 byte for sizes below 64, and at most 8 bytes for sizes up to 2^62 - 1.
+	Varint,
+	/// A fixed 4 byte little-endian `u32`, as written by versions of socknet before varint framing.
+	/// Only use this to communicate with peers which have not been updated.
+	Fixed32,
+}
+
+// Deriving needs `#[default]` on the variant, which would raise the minimum supported Rust version to 1.62.
+#[allow(clippy::derivable_impls)]
+impl Default for Framing {
+	fn default() -> Self {
+		Self::Varint
+	}
+}
+
+impl Framing {
+	/// The largest size which can be encoded as a varint.
+	pub const MAX_VARINT: u64 = (1 << 62) - 1;
+
+	/// Encodes the size of a message into its header.
+	/// Fails if the size cannot be represented by the framing, instead of truncating it.
+	pub fn encode(&self, size: usize) -> Result<Vec<u8>, Error> {
+		match self {
+			Self::Varint => {
+				let value = size as u64;
+				Ok(if value < (1 << 6) {
+					vec![value as u8]
+				} else if value < (1 << 14) {
+					((value as u16) | (0b01 << 14)).to_be_bytes().to_vec()
+				} else if value < (1 << 30) {
+					((value as u32) | (0b10 << 30)).to_be_bytes().to_vec()
+				} else if value <= Self::MAX_VARINT {
+					(value | (0b11 << 62)).to_be_bytes().to_vec()
+				} else {
+					return Err(Error::SizeNotEncodable(size));
+				})
+			}
+			Self::Fixed32 => {
+				let value = u32::try_from(size).map_err(|_| Error::SizeNotEncodable(size))?;
+				Ok(value.to_le_bytes().to_vec())
+			}
+		}
+	}
+
+	/// Returns the total number of bytes in a header, given the first byte of the header.
+	pub fn header_len(&self, first: u8) -> usize {
+		match self {
+			Self::Varint => 1 << (first >> 6),
+			Self::Fixed32 => std::mem::size_of::<u32>(),
+		}
+	}
+
+	/// Decodes the size of a message from its full header (whose length is given by [`header_len`](Self::header_len)).
+	pub fn decode(&self, header: &[u8]) -> Result<usize, Error> {
+		let expected = match header.first() {
+			Some(first) => self.header_len(*first),
+			None => return Err(Error::InvalidHeader(header.len())),
+		};
+		if header.len() != expected {
+			return Err(Error::InvalidHeader(header.len()));
+		}
+		let value = match self {
+			Self::Varint => {
+				let mut bytes = [0u8; 8];
+				bytes[8 - header.len()..].copy_from_slice(header);
+				bytes[8 - header.len()] &= 0b0011_1111;
+				u64::from_be_bytes(bytes)
+			}
+			Self::Fixed32 => {
+				let mut bytes = [0u8; 4];
+				bytes.copy_from_slice(header);
+				u32::from_le_bytes(bytes) as u64
+			}
+		};
+		usize::try_from(value).map_err(|_| Error::SizeNotAddressable(value))
+	}
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+	#[error("Message size {0} cannot be encoded by the stream's framing.")]
+	SizeNotEncodable(usize),
+	#[error("Message size {0} cannot be addressed on this platform.")]
+	SizeNotAddressable(u64),
+	#[error("Size header of {0} bytes does not match the stream's framing.")]
+	InvalidHeader(usize),
+}
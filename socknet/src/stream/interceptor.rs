@@ -0,0 +1,157 @@
+use crate::{
+	connection::Connection,
+	stream::{capture, Deadline, TimedOut},
+};
+use std::{sync::Arc, time::Duration};
+use tokio::time::Instant;
+
+/// The stream which an [`Interceptor`] is called for.
+pub struct Interception<'a> {
+	/// The [`unique id`](crate::stream::Identifier::unique_id) of the stream's handler.
+	pub handler_id: &'a str,
+	pub connection: &'a Arc<Connection>,
+	pub kind: capture::Kind,
+	/// The [`code`](crate::stream::kind::code) an incoming stream is rejected with regardless of the interceptors,
+	/// if its handler is unknown or does not accept the connection
+	/// (such as [`PERMISSION_DENIED`](crate::stream::kind::code::PERMISSION_DENIED)).
+	/// Always `None` for outgoing streams.
+	pub rejection: Option<u32>,
+}
+
+/// What happens to a stream after an [`Interceptor`] has been called for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+	/// Calls the next interceptor, or continues to handle the stream if this was the last interceptor.
+	Continue,
+	/// Stops handling the stream, without calling any later interceptors.
+	///
+	/// Incoming streams are [`rejected`](crate::stream::kind::Kind::reject) with the provided [`code`](crate::stream::kind::code),
+	/// and opening an outgoing stream fails with [`Intercepted`](crate::stream::Error::Intercepted).
+	Reject(u32),
+}
+
+/// How handling a stream ended, reported to the [`Interceptor`]s which were called for it.
+#[derive(Debug)]
+pub enum Outcome<'a> {
+	/// The incoming stream was handed to its receiver, which is done with it (its context was dropped).
+	Received,
+	/// The receiver of the incoming stream exceeded the [`timeout`](crate::stream::Registration::timeout)
+	/// of its handler.
+	TimedOut(TimedOut),
+	/// The incoming stream was rejected with a [`code`](crate::stream::kind::code),
+	/// because it was [`rejected`](Interception::rejection) anyway, by an interceptor,
+	/// or by the [`limits`](crate::stream::Limits) of its handler.
+	Rejected(u32),
+	/// The incoming stream was dropped, because it exceeded the [`limits`](crate::stream::Limits) of its handler.
+	Dropped,
+	/// The outgoing stream was opened and identified, so it was handed to its handler.
+	Opened,
+	/// Opening the outgoing stream failed, including when an interceptor rejected it.
+	Failed(&'a anyhow::Error),
+}
+
+/// Wraps every handler of a [`Registry`](crate::stream::Registry) with some cross-cutting behavior,
+/// like metrics, logging, or rate limiting.
+///
+/// Interceptors are added with [`intercept`](crate::stream::Registry::intercept),
+/// and are called in the order they were added.
+/// A connection whose registry was [`replaced`](Connection::set_registry) calls the interceptors
+/// of its endpoint's registry, and then those of its replacement.
+///
+/// Incoming streams are intercepted as soon as their handler id is read, including the streams which are
+/// [`rejected`](Interception::rejection) anyway (in which case the [`Flow`] is ignored).
+/// Otherwise the stream continues to the limits of its handler, and then the
+/// [`receiver`](crate::stream::handler::Receiver) is created.
+/// Outgoing streams are intercepted by [`open_context`](crate::stream::Identifier::open_context),
+/// before the stream is opened.
+///
+/// Once handling a stream ends, every interceptor which was called for it is [`completed`](Self::completed),
+/// so interceptors can measure how long streams take and see how they ended.
+///
+/// Both methods continue by default, and nothing happens on completion by default.
+pub trait Interceptor: Send + Sync {
+	/// Called for a stream opened by the peer.
+	fn receive(&self, _stream: &Interception) -> Flow {
+		Flow::Continue
+	}
+
+	/// Called for a stream opened by a handler.
+	fn open(&self, _stream: &Interception) -> Flow {
+		Flow::Continue
+	}
+
+	/// Called once handling a stream ends, with how long it took since the interceptors were called for it.
+	///
+	/// Incoming streams end when they are rejected or dropped, or once their receiver drops its
+	/// [`context`](crate::stream::Context). Outgoing streams end once they are opened, or fail to be.
+	fn completed(&self, _stream: &Interception, _outcome: &Outcome, _elapsed: Duration) {}
+}
+
+/// The interceptors which were called for a stream, which are [`completed`](Interceptor::completed)
+/// once handling the stream ends.
+pub(crate) struct Completion {
+	called: Vec<Arc<dyn Interceptor>>,
+	handler_id: String,
+	connection: Arc<Connection>,
+	kind: capture::Kind,
+	rejection: Option<u32>,
+	started: Instant,
+	/// The deadline of the stream's receiver, which decides if the receiver timed out.
+	deadline: Option<Deadline>,
+}
+
+impl Completion {
+	/// Calls each interceptor in order, until one of them rejects the stream.
+	pub(crate) fn run<F>(
+		interceptors: Vec<Arc<dyn Interceptor>>,
+		stream: &Interception,
+		call: F,
+	) -> (Flow, Self)
+	where
+		F: Fn(&dyn Interceptor, &Interception) -> Flow,
+	{
+		let mut completion = Self {
+			called: Vec::with_capacity(interceptors.len()),
+			handler_id: stream.handler_id.to_owned(),
+			connection: stream.connection.clone(),
+			kind: stream.kind,
+			rejection: stream.rejection,
+			started: Instant::now(),
+			deadline: None,
+		};
+		for interceptor in interceptors {
+			let flow = call(interceptor.as_ref(), stream);
+			completion.called.push(interceptor);
+			if let Flow::Reject(code) = flow {
+				return (Flow::Reject(code), completion);
+			}
+		}
+		(Flow::Continue, completion)
+	}
+
+	pub(crate) fn set_deadline(&mut self, deadline: Deadline) {
+		self.deadline = Some(deadline);
+	}
+
+	pub(crate) fn complete(self, outcome: &Outcome) {
+		let stream = Interception {
+			handler_id: &self.handler_id,
+			connection: &self.connection,
+			kind: self.kind,
+			rejection: self.rejection,
+		};
+		let elapsed = self.started.elapsed();
+		for interceptor in &self.called {
+			interceptor.completed(&stream, outcome, elapsed);
+		}
+	}
+
+	/// Completes an incoming stream whose receiver is done with it.
+	pub(crate) fn receiver_dropped(self) {
+		let outcome = match self.deadline.as_ref().and_then(Deadline::expired) {
+			Some(expired) => Outcome::TimedOut(expired),
+			None => Outcome::Received,
+		};
+		self.complete(&outcome);
+	}
+}
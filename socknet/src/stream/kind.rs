@@ -3,6 +3,14 @@ use crate::stream::local;
 mod locality;
 pub use locality::*;
 
+mod configure;
+pub use configure::*;
+
+pub mod code;
+
+mod reset;
+pub use reset::*;
+
 pub mod recv;
 pub use recv::{Read, Recv};
 
@@ -65,11 +73,51 @@ impl From<Vec<local::AnyBox>> for Kind {
 }
 
 impl Kind {
+	/// Reads the id of the handler which opened the stream.
+	/// Handler ids are always encoded using [`Bincode`](crate::stream::codec::Bincode),
+	/// regardless of the codec configured for the stream, and are limited to
+	/// [`max_handler_id_size`](crate::stream::Config::max_handler_id_size) bytes.
 	pub async fn read_handler_id(&mut self) -> anyhow::Result<String> {
-		Ok(match self {
-			Self::Unidirectional(recv) => recv.read::<String>().await?,
-			Self::Bidirectional((_send, recv)) => recv.read::<String>().await?,
-			Self::Datagram(recv) => recv.read::<String>().await?,
-		})
+		use crate::stream::{codec::Bincode, Config};
+		// The handler id is read with a much smaller size limit than the messages of the handler.
+		let config = self.config().clone();
+		self.configure(&Config {
+			max_message_size: config.max_handler_id_size,
+			..config.clone()
+		});
+		let handler_id = match self {
+			Self::Unidirectional(recv) => recv.read_with::<_, String>(&Bincode).await,
+			Self::Bidirectional((_send, recv)) => recv.read_with::<_, String>(&Bincode).await,
+			Self::Datagram(recv) => recv.read_with::<_, String>(&Bincode).await,
+		};
+		self.configure(&config);
+		handler_id
+	}
+
+	/// Stops reading from the stream, notifying the peer (where possible) with the provided [`code`](code).
+	pub async fn reject(&mut self, code: u32) -> anyhow::Result<()> {
+		match self {
+			Self::Unidirectional(recv) => recv.reject(code).await,
+			Self::Bidirectional((_send, recv)) => recv.reject(code).await,
+			Self::Datagram(recv) => recv.reject(code).await,
+		}
+	}
+}
+
+impl Configure for Kind {
+	fn config(&self) -> &crate::stream::Config {
+		match self {
+			Self::Unidirectional(recv) => recv.config(),
+			Self::Bidirectional(streams) => streams.config(),
+			Self::Datagram(recv) => recv.config(),
+		}
+	}
+
+	fn configure(&mut self, config: &crate::stream::Config) {
+		match self {
+			Self::Unidirectional(recv) => recv.configure(config),
+			Self::Bidirectional(streams) => streams.configure(config),
+			Self::Datagram(recv) => recv.configure(config),
+		}
 	}
 }
@@ -0,0 +1,36 @@
+//! Error codes sent to a peer when a stream is stopped or reset.
+//!
+//! Codes below [`APPLICATION_START`] are reserved by socknet (including the codes which later versions may define),
+//! so an application which stops or resets streams with its own codes should create them with [`application`],
+//! which can never be mistaken for a code of socknet (such as [`TIMED_OUT`]).
+//!
+//! Stream codes are limited to 32 bits, so both socknet and the application get 16 bits of codes,
+//! unlike [`CloseCode`](crate::connection::CloseCode), which is sent as a wider varint and has wider ranges.
+
+/// The stream was stopped without any error.
+pub const NONE: u32 = 0;
+/// The peer sent data which does not follow the socknet protocol,
+/// such as a message which is larger than the configured limits.
+pub const PROTOCOL_VIOLATION: u32 = 1;
+/// The stream was opened for a handler which is not in the [`registry`](crate::connection::Connection::registry)
+/// of the connection, so the handler is unknown or cannot be used in the connection's current state.
+pub const UNKNOWN_HANDLER: u32 = 2;
+/// The stream was opened for a handler which the connection is not authorized to use
+/// (see [`register_authorized`](crate::stream::Registry::register_authorized)).
+pub const PERMISSION_DENIED: u32 = 3;
+/// The stream was opened for a handler which does not accept streams from the role of the peer
+/// (see [`accept_from`](crate::stream::Registration::accept_from)).
+pub const WRONG_DIRECTION: u32 = 4;
+/// The stream exceeded the [`limits`](crate::stream::Limits) of its handler or connection,
+/// whose [`overflow`](crate::stream::Overflow::Reset) policy is to reset streams.
+pub const LIMIT_EXCEEDED: u32 = 5;
+/// The handler of the stream exceeded its [`timeout`](crate::stream::Registration::timeout).
+pub const TIMED_OUT: u32 = 6;
+
+/// The first code of the range which the application can define (see [`application`]).
+pub const APPLICATION_START: u32 = 1 << 16;
+
+/// The code which is sent to the peer for a code defined by the application.
+pub const fn application(code: u16) -> u32 {
+	APPLICATION_START + code as u32
+}
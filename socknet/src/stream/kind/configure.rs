@@ -0,0 +1,10 @@
+use crate::stream::Config;
+
+/// Provides access to the [`configuration`](Config) used when writing to or reading from a stream.
+pub trait Configure {
+	/// Returns the configuration currently applied to the stream.
+	fn config(&self) -> &Config;
+
+	/// Replaces the configuration applied to the stream.
+	fn configure(&mut self, config: &Config);
+}
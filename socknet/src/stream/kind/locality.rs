@@ -1,7 +1,12 @@
 use crate::{
-	stream::kind::{
-		recv::{Read, Recv},
-		send::{Send, Write},
+	stream::{
+		codec::Codec,
+		kind::{
+			recv::{Read, Recv},
+			send::{Send, Write},
+			Configure,
+		},
+		Config,
 	},
 	utility::PinFutureResultLifetime,
 };
@@ -11,6 +16,26 @@ pub enum Locality<R, L> {
 	Local(L),
 }
 
+impl<R, L> Configure for Locality<R, L>
+where
+	R: Configure,
+	L: Configure,
+{
+	fn config(&self) -> &Config {
+		match self {
+			Self::Remote(remote) => remote.config(),
+			Self::Local(local) => local.config(),
+		}
+	}
+
+	fn configure(&mut self, config: &Config) {
+		match self {
+			Self::Remote(remote) => remote.configure(config),
+			Self::Local(local) => local.configure(config),
+		}
+	}
+}
+
 impl<R, L> Send for Locality<R, L>
 where
 	R: Send + std::marker::Send + 'static,
@@ -22,6 +47,13 @@ where
 			Self::Local(local) => local.finish(),
 		}
 	}
+
+	fn reset<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()> {
+		match self {
+			Self::Remote(remote) => remote.reset(code),
+			Self::Local(local) => local.reset(code),
+		}
+	}
 }
 
 impl<R, L> Recv for Locality<R, L>
@@ -29,10 +61,10 @@ where
 	R: Recv + std::marker::Send + 'static,
 	L: Recv + std::marker::Send + 'static,
 {
-	fn stop<'a>(&'a mut self) -> PinFutureResultLifetime<'a, ()> {
+	fn stop<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()> {
 		match self {
-			Self::Remote(remote) => remote.stop(),
-			Self::Local(local) => local.stop(),
+			Self::Remote(remote) => remote.stop(code),
+			Self::Local(local) => local.stop(code),
 		}
 	}
 }
@@ -49,6 +81,13 @@ where
 		}
 	}
 
+	fn abort<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()> {
+		match self {
+			Self::Remote(remote) => remote.abort(code),
+			Self::Local(local) => local.abort(code),
+		}
+	}
+
 	fn write_size<'a>(&'a mut self, len: usize) -> PinFutureResultLifetime<'a, ()> {
 		match self {
 			Self::Remote(remote) => remote.write_size(len),
@@ -73,6 +112,22 @@ where
 			Self::Local(local) => local.write(data),
 		}
 	}
+
+	fn write_with<'a, C, T>(
+		&'a mut self,
+		codec: &'a C,
+		data: &'a T,
+	) -> PinFutureResultLifetime<'a, ()>
+	where
+		Self: std::marker::Send,
+		C: Codec + Sync,
+		T: 'static + serde::Serialize + Clone + std::marker::Send + Sync,
+	{
+		match self {
+			Self::Remote(remote) => remote.write_with(codec, data),
+			Self::Local(local) => local.write_with(codec, data),
+		}
+	}
 }
 
 impl<R, L> Read for Locality<R, L>
@@ -101,6 +156,13 @@ where
 		}
 	}
 
+	fn reject<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()> {
+		match self {
+			Self::Remote(remote) => remote.reject(code),
+			Self::Local(local) => local.reject(code),
+		}
+	}
+
 	fn read<'a, T>(&'a mut self) -> PinFutureResultLifetime<'a, T>
 	where
 		T: serde::de::DeserializeOwned + Sized + std::marker::Send + Sync + 'static,
@@ -110,17 +172,51 @@ where
 			Self::Local(local) => local.read(),
 		}
 	}
+
+	fn read_with<'a, C, T>(&'a mut self, codec: &'a C) -> PinFutureResultLifetime<'a, T>
+	where
+		C: Codec + Sync,
+		T: serde::de::DeserializeOwned + Sized + std::marker::Send + Sync + 'static,
+	{
+		match self {
+			Self::Remote(remote) => remote.read_with(codec),
+			Self::Local(local) => local.read_with(codec),
+		}
+	}
+}
+
+impl<RSend, LSend, RRecv, LRecv> Configure for (Locality<RSend, LSend>, Locality<RRecv, LRecv>)
+where
+	RSend: Configure,
+	LSend: Configure,
+	RRecv: Configure,
+	LRecv: Configure,
+{
+	fn config(&self) -> &Config {
+		self.0.config()
+	}
+
+	fn configure(&mut self, config: &Config) {
+		self.0.configure(config);
+		self.1.configure(config);
+	}
 }
 
 impl<RSend, LSend, RRecv, LRecv> Write for (Locality<RSend, LSend>, Locality<RRecv, LRecv>)
 where
 	RSend: Write + std::marker::Send + 'static,
 	LSend: Write + std::marker::Send + 'static,
+	RRecv: Configure,
+	LRecv: Configure,
 {
 	fn write_exact<'a>(&'a mut self, buf: &'a [u8]) -> PinFutureResultLifetime<'a, ()> {
 		self.0.write_exact(buf)
 	}
 
+	fn abort<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()> {
+		self.0.abort(code)
+	}
+
 	fn write_size<'a>(&'a mut self, len: usize) -> PinFutureResultLifetime<'a, ()> {
 		self.0.write_size(len)
 	}
@@ -136,10 +232,25 @@ where
 	{
 		self.0.write(data)
 	}
+
+	fn write_with<'a, C, T>(
+		&'a mut self,
+		codec: &'a C,
+		data: &'a T,
+	) -> PinFutureResultLifetime<'a, ()>
+	where
+		Self: std::marker::Send,
+		C: Codec + Sync,
+		T: 'static + serde::Serialize + Clone + std::marker::Send + Sync,
+	{
+		self.0.write_with(codec, data)
+	}
 }
 
 impl<RSend, LSend, RRecv, LRecv> Read for (Locality<RSend, LSend>, Locality<RRecv, LRecv>)
 where
+	RSend: Configure,
+	LSend: Configure,
 	RRecv: Read + std::marker::Send + 'static,
 	LRecv: Read + std::marker::Send + 'static,
 {
@@ -155,10 +266,22 @@ where
 		self.1.read_bytes()
 	}
 
+	fn reject<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()> {
+		self.1.reject(code)
+	}
+
 	fn read<'a, T>(&'a mut self) -> PinFutureResultLifetime<'a, T>
 	where
 		T: serde::de::DeserializeOwned + Sized + std::marker::Send + Sync + 'static,
 	{
 		self.1.read()
 	}
+
+	fn read_with<'a, C, T>(&'a mut self, codec: &'a C) -> PinFutureResultLifetime<'a, T>
+	where
+		C: Codec + Sync,
+		T: serde::de::DeserializeOwned + Sized + std::marker::Send + Sync + 'static,
+	{
+		self.1.read_with(codec)
+	}
 }
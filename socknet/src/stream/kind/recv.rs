@@ -4,6 +4,10 @@ use crate::utility::PinFutureResultLifetime;
 mod read;
 pub use read::*;
 
+#[doc(hidden)]
+mod error;
+pub use error::*;
+
 pub mod ongoing;
 pub use ongoing::Ongoing;
 
@@ -11,6 +15,9 @@ pub mod datagram;
 pub use datagram::Datagram;
 
 pub trait Recv {
-	/// Stop accepting data. Discards unread data and notifies the peer to stop transmitting.
-	fn stop<'a>(&'a mut self) -> PinFutureResultLifetime<'a, ()>;
+	/// Stop accepting data. Discards unread data and notifies the peer to stop transmitting,
+	/// with an application error [`code`](super::code) (such as [`NONE`](super::code::NONE)).
+	/// Codes defined by the application should be created with [`application`](super::code::application).
+	/// The peer's later writes fail with [`StreamReset`](super::StreamReset).
+	fn stop<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()>;
 }
@@ -1,68 +1,114 @@
 use crate::{
 	stream::{
-		kind::recv::{Read, Recv},
-		local,
+		kind::{
+			recv::{read::serialized, Error, Read, Recv},
+			Configure,
+		},
+		local, Config,
 	},
 	utility::PinFutureResultLifetime,
 };
 
-pub struct Local(Vec<local::AnyBox>);
+/// The contents of a local datagram.
+/// When [`serialize_local`](Config::serialize_local) is enabled, the items are chunks of bytes,
+/// which are buffered so they can be read in arbitrary sizes (like a remote datagram).
+pub struct Local(Vec<local::AnyBox>, Config, Vec<u8>);
 
 impl From<Vec<local::AnyBox>> for Local {
 	fn from(stream: Vec<local::AnyBox>) -> Self {
-		Self(stream)
+		Self(stream, Config::default(), Vec::new())
+	}
+}
+
+impl Configure for Local {
+	fn config(&self) -> &Config {
+		&self.1
+	}
+
+	/// Local paths never compress their messages, even when they are [`serialized`](Config::serialize_local).
+	fn configure(&mut self, config: &Config) {
+		self.1 = Config {
+			compression: None,
+			..config.clone()
+		};
 	}
 }
 
 impl Local {
+	fn next(&mut self) -> Result<local::AnyBox, Error> {
+		if self.0.is_empty() {
+			return Err(Error::UnexpectedEof {
+				expected: 1,
+				available: 0,
+			});
+		}
+		Ok(self.0.remove(0))
+	}
+
 	fn read_any<'a, T>(&'a mut self) -> PinFutureResultLifetime<'a, T>
 	where
 		T: Send + Sync + 'static,
 	{
-		Box::pin(async move {
-			assert!(!self.0.is_empty());
-			let any = self.0.remove(0);
-			let byte_vec = any
-				.downcast::<T>()
-				.map_err(|_| LocalError::InvalidTypeEncountered)?;
-			Ok(*byte_vec)
-		})
+		Box::pin(async move { Ok(local::downcast::<T>(self.next()?)?) })
 	}
 }
 
 impl Read for Local {
 	fn read_exact<'a>(&'a mut self, byte_count: usize) -> PinFutureResultLifetime<'a, Vec<u8>> {
 		Box::pin(async move {
-			let byte_vec = self.read_any::<Vec<u8>>().await?;
-			assert_eq!(byte_vec.len(), byte_count);
-			Ok(byte_vec)
+			if !self.1.serialize_local {
+				return Ok(local::downcast_exact(self.next()?, byte_count)?);
+			}
+			while self.2.len() < byte_count {
+				if self.0.is_empty() {
+					return Err(Error::UnexpectedEof {
+						expected: byte_count,
+						available: self.2.len(),
+					})?;
+				}
+				let chunk = local::downcast::<Vec<u8>>(self.0.remove(0))?;
+				self.2.extend(chunk);
+			}
+			Ok(self.2.drain(..byte_count).collect())
 		})
 	}
 
 	fn read_size<'a>(&'a mut self) -> PinFutureResultLifetime<'a, usize> {
-		self.read_any::<usize>()
+		match self.1.serialize_local {
+			true => serialized::read_size(self),
+			false => self.read_any::<usize>(),
+		}
 	}
 
 	fn read_bytes<'a>(&'a mut self) -> PinFutureResultLifetime<'a, Vec<u8>> {
-		self.read_any::<Vec<u8>>()
+		match self.1.serialize_local {
+			true => serialized::read_bytes(self),
+			false => self.read_any::<Vec<u8>>(),
+		}
 	}
 
 	fn read<'a, T>(&'a mut self) -> PinFutureResultLifetime<'a, T>
 	where
 		T: serde::de::DeserializeOwned + Sized + Send + Sync + 'static,
 	{
-		self.read_any::<T>()
+		match self.1.serialize_local {
+			true => Box::pin(async move {
+				let codec = self.1.codec;
+				self.read_with(&codec).await
+			}),
+			false => self.read_any::<T>(),
+		}
 	}
 }
 
 impl Recv for Local {
-	fn stop<'a>(&'a mut self) -> PinFutureResultLifetime<'a, ()> {
-		Box::pin(async move { Ok(()) })
+	/// Discards the rest of the datagram, so any further reads fail.
+	/// The datagram has already been sent in full, so the peer is not notified.
+	fn stop<'a>(&'a mut self, _code: u32) -> PinFutureResultLifetime<'a, ()> {
+		Box::pin(async move {
+			self.0.clear();
+			self.2.clear();
+			Ok(())
+		})
 	}
 }
-
-#[derive(thiserror::Error, Debug)]
-pub enum LocalError {
-	#[error("Encountered a type in the stream which did not match the expected type")]
-	InvalidTypeEncountered,
-}
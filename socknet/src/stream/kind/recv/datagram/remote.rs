@@ -1,13 +1,29 @@
 use crate::{
-	stream::kind::recv::{Read, Recv},
+	stream::{
+		kind::{
+			recv::{Error, Read, Recv},
+			Configure,
+		},
+		Config,
+	},
 	utility::PinFutureResultLifetime,
 };
 
-pub struct Remote(bytes::Bytes);
+pub struct Remote(bytes::Bytes, Config);
 
 impl From<bytes::Bytes> for Remote {
 	fn from(stream: bytes::Bytes) -> Self {
-		Self(stream)
+		Self(stream, Config::default())
+	}
+}
+
+impl Configure for Remote {
+	fn config(&self) -> &Config {
+		&self.1
+	}
+
+	fn configure(&mut self, config: &Config) {
+		self.1 = config.clone();
 	}
 }
 
@@ -17,15 +33,29 @@ impl Read for Remote {
 	/// This operation has no internal awaits, and
 	/// utilizes [`split_to`](bytes::Bytes::split_to) to read the next
 	/// set of bytes from the internal buffer.
+	/// Fails with [`UnexpectedEof`](Error::UnexpectedEof) if the buffer has fewer than `byte_count` bytes left.
 	///
 	/// Mirrors [`write_exact`](crate::stream::kind::Write::write_exact).
 	fn read_exact<'a>(&'a mut self, byte_count: usize) -> PinFutureResultLifetime<'a, Vec<u8>> {
-		Box::pin(async move { Ok(self.0.split_to(byte_count).to_vec()) })
+		Box::pin(async move {
+			if byte_count > self.0.len() {
+				return Err(Error::UnexpectedEof {
+					expected: byte_count,
+					available: self.0.len(),
+				})?;
+			}
+			Ok(self.0.split_to(byte_count).to_vec())
+		})
 	}
 }
 
 impl Recv for Remote {
-	fn stop<'a>(&'a mut self) -> PinFutureResultLifetime<'a, ()> {
-		Box::pin(async move { Ok(()) })
+	/// Discards the rest of the datagram, so any further reads fail.
+	/// The datagram has already been sent in full, so the peer is not notified.
+	fn stop<'a>(&'a mut self, _code: u32) -> PinFutureResultLifetime<'a, ()> {
+		Box::pin(async move {
+			self.0.clear();
+			Ok(())
+		})
 	}
 }
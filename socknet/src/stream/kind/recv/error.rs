@@ -0,0 +1,33 @@
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+	#[error("Peer sent a message of {size} bytes, which exceeds the limit of {limit} bytes.")]
+	MessageTooLarge { size: usize, limit: usize },
+	/// The stream or buffer ended before the expected amount of data could be read.
+	/// Amounts are in bytes for remote streams, and in items for local streams.
+	#[error("Stream ended after {available} of the expected {expected} bytes/items were read.")]
+	UnexpectedEof { expected: usize, available: usize },
+	#[error("Peer sent malformed data: {0}")]
+	Malformed(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+	/// A local stream contained a value of a different type than the one being read,
+	/// meaning the writes and reads of the handler do not mirror each other.
+	#[error(
+		"Expected to read a value of type {expected} from a local stream, but found {actual}."
+	)]
+	TypeMismatch {
+		expected: &'static str,
+		actual: &'static str,
+	},
+	/// A local stream contained a byte buffer of a different length than the one being read,
+	/// meaning the writes and reads of the handler do not mirror each other.
+	#[error("Expected to read {expected} bytes from a local stream, but found {actual}.")]
+	LengthMismatch { expected: usize, actual: usize },
+}
+
+impl Error {
+	pub(crate) fn malformed<E>(error: E) -> Self
+	where
+		E: Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
+	{
+		Self::Malformed(error.into())
+	}
+}
@@ -1,71 +1,210 @@
 use crate::{
 	stream::{
-		kind::recv::{Read, Recv},
-		local,
+		kind::{
+			code,
+			recv::{read::serialized, Error, Read, Recv},
+			Configure, StreamReset,
+		},
+		local, Config, Deadline,
 	},
 	utility::PinFutureResultLifetime,
 };
 
-pub(crate) type Internal = async_channel::Receiver<local::AnyBox>;
-pub struct Local(Internal);
+/// The channel backing a local stream, the signal for when its connection is closed,
+/// and the codes the stream was stopped or reset with.
+pub(crate) struct Internal(
+	pub(crate) async_channel::Receiver<local::AnyBox>,
+	pub(crate) local::Closed,
+	pub(crate) local::Codes,
+);
+
+/// The receiving end of a local stream.
+/// When [`serialize_local`](Config::serialize_local) is enabled, the stream items are chunks of bytes,
+/// which are buffered so they can be read in arbitrary sizes (like a remote stream).
+pub struct Local(
+	async_channel::Receiver<local::AnyBox>,
+	Config,
+	Vec<u8>,
+	local::Closed,
+	/// If the items are always chunks of bytes, regardless of the config.
+	bool,
+	local::Codes,
+);
 
 impl From<Internal> for Local {
-	fn from(stream: Internal) -> Self {
-		Self(stream)
+	fn from(Internal(stream, closed, codes): Internal) -> Self {
+		Self(stream, Config::default(), Vec::new(), closed, false, codes)
+	}
+}
+
+impl Configure for Local {
+	fn config(&self) -> &Config {
+		&self.1
+	}
+
+	/// Local paths never compress their messages, except for the serialized messages of a [`replayed`](crate::stream::capture::replay) capture
+	/// (which are the bytes sent over the wire).
+	///
+	/// The stream is stopped with [`TIMED_OUT`](code::TIMED_OUT) once its [`deadline`](Config::deadline) expires.
+	fn configure(&mut self, config: &Config) {
+		if let Some(deadline) = Deadline::added(&self.1.deadline, &config.deadline) {
+			let codes = self.5.clone();
+			deadline.on_expired(move || codes.stop(code::TIMED_OUT));
+		}
+		self.1 = config.clone();
+		if !self.4 {
+			self.1.compression = None;
+		}
 	}
 }
 
 impl Local {
+	/// Creates a stream whose items are always chunks of bytes (like a remote stream),
+	/// such as the messages of a [`replayed`](crate::stream::capture::replay) capture.
+	pub(crate) fn serialized(
+		stream: async_channel::Receiver<local::AnyBox>,
+		closed: local::Closed,
+	) -> Self {
+		Self(
+			stream,
+			Config::default(),
+			Vec::new(),
+			closed,
+			true,
+			local::Codes::default(),
+		)
+	}
+
+	fn is_serialized(&self) -> bool {
+		self.4 || self.1.serialize_local
+	}
+
+	/// Waits for the next item in the stream, which is `None` if the writer has finished.
+	/// Fails if the connection is closed or the writer reset the stream, even if there are items remaining.
+	/// If the stream has a [`deadline`](Config::deadline) which expires first,
+	/// the stream is stopped with [`TIMED_OUT`](code::TIMED_OUT).
+	async fn recv(&mut self) -> anyhow::Result<Option<local::AnyBox>> {
+		if let Some(error) = self.3.error() {
+			return Err(error)?;
+		}
+		if let Some(code) = self.5.was_reset() {
+			return Err(StreamReset::error(code));
+		}
+		let (stream, closed, codes) = (&self.0, &mut self.3, &self.5);
+		let recv = async move {
+			tokio::select! {
+				item = stream.recv() => Ok(item.ok()),
+				error = closed.wait() => Err(error),
+				// Reported as a reset below.
+				_ = codes.wait_reset() => Ok(None),
+			}
+		};
+		let item = match &self.1.deadline {
+			Some(deadline) => match deadline.bound(recv).await {
+				Ok(item) => item,
+				Err(expired) => {
+					self.end(code::TIMED_OUT);
+					return Err(expired)?;
+				}
+			},
+			None => recv.await,
+		}?;
+		// The writer may have reset the stream (which closes it) while this was waiting.
+		match (item, self.5.was_reset()) {
+			(None, Some(code)) => Err(StreamReset::error(code)),
+			(item, _) => Ok(item),
+		}
+	}
+
+	/// Stops the stream with a code, discarding any unread items so all later reads fail.
+	fn end(&mut self, code: u32) {
+		self.5.stop(code);
+		self.0.close();
+		while self.0.try_recv().is_ok() {}
+		self.2.clear();
+	}
+
+	async fn next(&mut self) -> anyhow::Result<local::AnyBox> {
+		match self.recv().await? {
+			Some(item) => Ok(item),
+			None => Err(Error::UnexpectedEof {
+				expected: 1,
+				available: 0,
+			})?,
+		}
+	}
+
 	fn read_any<'a, T>(&'a mut self) -> PinFutureResultLifetime<'a, T>
 	where
 		T: 'static + Send + Sync,
 	{
-		Box::pin(async move {
-			let any = self.0.recv().await?;
-			let byte_vec = any
-				.downcast::<T>()
-				.map_err(|_| LocalError::InvalidTypeEncountered)?;
-			Ok(*byte_vec)
-		})
+		Box::pin(async move { Ok(local::downcast::<T>(self.next().await?)?) })
 	}
 }
 
 impl Read for Local {
 	fn read_exact<'a>(&'a mut self, byte_count: usize) -> PinFutureResultLifetime<'a, Vec<u8>> {
 		Box::pin(async move {
-			let byte_vec = self.read_any::<Vec<u8>>().await?;
-			assert_eq!(byte_vec.len(), byte_count);
-			Ok(byte_vec)
+			if !self.is_serialized() {
+				return Ok(local::downcast_exact(self.next().await?, byte_count)?);
+			}
+			while self.2.len() < byte_count {
+				let chunk = match self.recv().await? {
+					Some(item) => local::downcast::<Vec<u8>>(item)?,
+					None => {
+						return Err(Error::UnexpectedEof {
+							expected: byte_count,
+							available: self.2.len(),
+						})?
+					}
+				};
+				self.2.extend(chunk);
+			}
+			Ok(self.2.drain(..byte_count).collect())
 		})
 	}
 
 	fn read_size<'a>(&'a mut self) -> PinFutureResultLifetime<'a, usize> {
-		self.read_any::<usize>()
+		match self.is_serialized() {
+			true => serialized::read_size(self),
+			false => self.read_any::<usize>(),
+		}
 	}
 
 	fn read_bytes<'a>(&'a mut self) -> PinFutureResultLifetime<'a, Vec<u8>> {
-		self.read_any::<Vec<u8>>()
+		match self.is_serialized() {
+			true => serialized::read_bytes(self),
+			false => self.read_any::<Vec<u8>>(),
+		}
 	}
 
 	fn read<'a, T>(&'a mut self) -> PinFutureResultLifetime<'a, T>
 	where
 		T: serde::de::DeserializeOwned + Sized + Send + Sync + 'static,
 	{
-		self.read_any::<T>()
+		match self.is_serialized() {
+			true => Box::pin(async move {
+				let codec = self.1.codec;
+				self.read_with(&codec).await
+			}),
+			false => self.read_any::<T>(),
+		}
+	}
+
+	/// Stops the stream, discarding any unread items so all later reads fail.
+	/// The writer's later writes fail with [`StreamReset`] (like a remote stream).
+	fn reject<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()> {
+		self.stop(code)
 	}
 }
 
 impl Recv for Local {
-	fn stop<'a>(&'a mut self) -> PinFutureResultLifetime<'a, ()> {
+	/// Stops the stream, discarding any unread items so all later reads fail.
+	/// The writer's later writes fail with [`StreamReset`] (like a remote stream).
+	fn stop<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()> {
 		Box::pin(async move {
-			self.0.close();
+			self.end(code);
 			Ok(())
 		})
 	}
 }
-
-#[derive(thiserror::Error, Debug)]
-pub enum LocalError {
-	#[error("Encountered a type in the stream which did not match the expected type")]
-	InvalidTypeEncountered,
-}
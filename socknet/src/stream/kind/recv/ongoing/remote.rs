@@ -1,36 +1,118 @@
 use crate::{
-	stream::kind::recv::{Read, Recv},
+	stream::{
+		kind::{
+			code,
+			recv::{Error, Read, Recv},
+			Configure, StreamReset,
+		},
+		Config, Deadline,
+	},
 	utility::PinFutureResultLifetime,
 };
+use std::sync::Arc;
 
-pub struct Remote(quinn::RecvStream);
+/// The stream is shared with its [`deadline`](Config::deadline), which stops the stream once it expires.
+pub struct Remote(Arc<tokio::sync::Mutex<quinn::RecvStream>>, Config);
+
+/// Converts the error of a read, so that the peer resetting the stream is a [`StreamReset`] error.
+fn read_error(error: quinn::ReadError) -> anyhow::Error {
+	match error {
+		quinn::ReadError::Reset(code) => StreamReset::from_remote(code),
+		error => error.into(),
+	}
+}
 
 impl From<quinn::RecvStream> for Remote {
 	fn from(stream: quinn::RecvStream) -> Self {
-		Self(stream)
+		Self(Arc::new(tokio::sync::Mutex::new(stream)), Config::default())
+	}
+}
+
+impl Configure for Remote {
+	fn config(&self) -> &Config {
+		&self.1
+	}
+
+	fn configure(&mut self, config: &Config) {
+		if let Some(deadline) = Deadline::added(&self.1.deadline, &config.deadline) {
+			let stream = Arc::downgrade(&self.0);
+			deadline.on_expired(move || {
+				// A stream which is in use is stopped by the operation, which is bounded by the same deadline.
+				if let Some(Ok(mut stream)) =
+					stream.upgrade().as_ref().map(|stream| stream.try_lock())
+				{
+					let _ = stream.stop(quinn::VarInt::from_u32(code::TIMED_OUT));
+				}
+			});
+		}
+		self.1 = config.clone();
 	}
 }
 
 impl Read for Remote {
 	/// Reads an explicit number of bytes from the stream.
+	/// Fails with [`UnexpectedEof`](Error::UnexpectedEof) if the stream is finished before all bytes are read.
+	/// If the stream has a [`deadline`](Config::deadline) which expires first,
+	/// the stream is stopped with [`TIMED_OUT`](code::TIMED_OUT).
 	///
 	/// Mirrors [`write_exact`](crate::stream::kind::Write::write_exact).
 	///
-	/// See [`quinn`](quinn::RecvStream::read_exact) for more details.
+	/// See [`quinn`](quinn::RecvStream::read) for more details.
 	fn read_exact<'a>(&'a mut self, byte_count: usize) -> PinFutureResultLifetime<'a, Vec<u8>> {
 		Box::pin(async move {
-			let mut bytes = vec![0; byte_count];
-			self.0.read_exact(&mut bytes).await?;
-			Ok(bytes)
+			let deadline = self.1.deadline.clone();
+			let stream = &self.0;
+			let read = async move {
+				let mut stream = stream.lock().await;
+				let mut bytes = vec![0; byte_count];
+				let mut available = 0;
+				while available < byte_count {
+					match stream
+						.read(&mut bytes[available..])
+						.await
+						.map_err(read_error)?
+					{
+						Some(count) => available += count,
+						None => {
+							return Err(Error::UnexpectedEof {
+								expected: byte_count,
+								available,
+							})?;
+						}
+					}
+				}
+				Ok(bytes)
+			};
+			match deadline {
+				Some(deadline) => match deadline.bound(read).await {
+					Ok(read) => read,
+					Err(expired) => {
+						let _ = self
+							.0
+							.lock()
+							.await
+							.stop(quinn::VarInt::from_u32(code::TIMED_OUT));
+						Err(expired)?
+					}
+				},
+				None => read.await,
+			}
 		})
 	}
+
+	/// Stops the stream, notifying the peer of the provided code.
+	///
+	/// See [`quinn`](quinn::RecvStream::stop) for more details.
+	fn reject<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()> {
+		self.stop(code)
+	}
 }
 
 impl Recv for Remote {
 	/// Stop accepting data. Discards unread data and notifies the peer to stop transmitting.
 	///
 	/// See [`quinn`](quinn::RecvStream::stop) for more.
-	fn stop<'a>(&'a mut self) -> PinFutureResultLifetime<'a, ()> {
-		Box::pin(async move { Ok(self.0.stop(quinn::VarInt::from_u32(0))?) })
+	fn stop<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()> {
+		Box::pin(async move { Ok(self.0.lock().await.stop(quinn::VarInt::from_u32(code))?) })
 	}
 }
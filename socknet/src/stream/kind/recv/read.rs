@@ -1,13 +1,22 @@
-use crate::utility::PinFutureResultLifetime;
+use crate::{
+	stream::{
+		codec::Codec,
+		kind::{code, recv::Error, Configure},
+		TimedOut,
+	},
+	utility::PinFutureResultLifetime,
+};
+use std::time::Duration;
+use tokio::time::Instant;
 
 /// Local interface for providing the async-read functionality to recv streams.
-pub trait Read {
+pub trait Read: Configure {
 	/// Reads an explicit number of bytes from the stream.
 	///
 	/// Mirrors [`write_exact`](crate::stream::kind::Write::write_exact).
 	fn read_exact<'a>(&'a mut self, byte_count: usize) -> PinFutureResultLifetime<'a, Vec<u8>>;
 
-	/// Reads a usize from the stream, encoded at a specific byte length.
+	/// Reads a usize from the stream, encoded by the [`framing`](crate::stream::Config::framing) of the stream.
 	///
 	/// Used primarily internally to read the sizes of byte vecs and generic structs to the stream.
 	///
@@ -16,15 +25,16 @@ pub trait Read {
 	where
 		Self: Send,
 	{
-		Box::pin(async move {
-			let len_encoded_size = std::mem::size_of::<u32>();
-			let encoded = self.read_exact(len_encoded_size).await?;
-			let size: u32 = bincode::deserialize(&encoded[..])?;
-			Ok(size as usize)
-		})
+		serialized::read_size(self)
 	}
 
 	/// Reads a set of bytes as a distinct vec, prefixed with a size header.
+	/// If [`compression`](crate::stream::Config::compression) is configured for the stream,
+	/// the bytes are decompressed according to their compression header.
+	///
+	/// If the size header (or decompressed size) exceeds the [`max_message_size`](crate::stream::Config::max_message_size),
+	/// the stream is [`rejected`](Self::reject) and [`MessageTooLarge`](Error::MessageTooLarge) is returned,
+	/// without allocating a buffer for the message.
 	///
 	/// This is different than [`read_exact`](Self::read_exact) because it reads a distinct header between set of bytes.
 	///
@@ -33,25 +43,152 @@ pub trait Read {
 	where
 		Self: Send,
 	{
-		Box::pin(async move {
-			let byte_count = self.read_size().await?;
-			self.read_exact(byte_count).await
-		})
+		serialized::read_bytes(self)
+	}
+
+	/// Stops reading from the stream because the peer violated the protocol,
+	/// notifying the peer (where possible) with the provided [`code`](crate::stream::kind::code).
+	///
+	/// Does nothing by default, which is the case for streams that have no peer to notify (like datagrams).
+	fn reject<'a>(&'a mut self, _code: u32) -> PinFutureResultLifetime<'a, ()> {
+		Box::pin(async move { Ok(()) })
 	}
 
-	/// Reads some generic sized data from the stream, prefixed with a size header.
+	/// Reads some generic sized data from the stream, prefixed with a size header,
+	/// and decoded using the [`codec`](crate::stream::Config::codec) configured for the stream.
 	///
 	/// Mirrors [`write`](crate::stream::kind::Write::write).
 	fn read<'a, T>(&'a mut self) -> PinFutureResultLifetime<'a, T>
 	where
 		Self: Send,
 		T: serde::de::DeserializeOwned + Sized + Send + Sync + 'static,
+	{
+		Box::pin(async move {
+			let codec = self.config().codec;
+			self.read_with(&codec).await
+		})
+	}
+
+	/// Reads some generic sized data from the stream, prefixed with a size header,
+	/// and decoded using the provided codec.
+	/// Data which cannot be decoded results in a [`Malformed`](Error::Malformed) error.
+	///
+	/// Mirrors [`write_with`](crate::stream::kind::Write::write_with).
+	fn read_with<'a, C, T>(&'a mut self, codec: &'a C) -> PinFutureResultLifetime<'a, T>
+	where
+		Self: Send,
+		C: Codec + Sync,
+		T: serde::de::DeserializeOwned + Sized + Send + Sync + 'static,
 	{
 		Box::pin(async move {
 			let encoded = self.read_bytes().await?;
 			// Convert data bytes to type
-			let data: T = bincode::deserialize(&encoded[..])?;
+			let data: T = codec.decode(&encoded[..]).map_err(Error::malformed)?;
 			Ok(data)
 		})
 	}
+
+	/// Reads some generic sized data like [`read`](Self::read), unless the deadline passes first.
+	///
+	/// A read which does not complete in time may have consumed part of a message,
+	/// so the stream is [`rejected`](Self::reject) with [`TIMED_OUT`](code::TIMED_OUT)
+	/// and [`TimedOut::Deadline`] is returned. Any later reads fail.
+	/// Datagrams are read from memory, so their reads always complete before the deadline.
+	///
+	/// Mirrors [`write_deadline`](crate::stream::kind::Write::write_deadline).
+	fn read_deadline<'a, T>(&'a mut self, deadline: Instant) -> PinFutureResultLifetime<'a, T>
+	where
+		Self: Send,
+		T: serde::de::DeserializeOwned + Sized + Send + Sync + 'static,
+	{
+		Box::pin(async move {
+			let read = tokio::time::timeout_at(deadline, self.read::<T>()).await;
+			match read {
+				Ok(read) => read,
+				Err(_) => {
+					let _ = self.reject(code::TIMED_OUT).await;
+					Err(TimedOut::Deadline)?
+				}
+			}
+		})
+	}
+
+	/// Reads some generic sized data like [`read`](Self::read), unless it takes longer than the timeout.
+	/// See [`read_deadline`](Self::read_deadline) for the state of the stream if it times out.
+	///
+	/// Mirrors [`write_timeout`](crate::stream::kind::Write::write_timeout).
+	fn read_timeout<'a, T>(&'a mut self, timeout: Duration) -> PinFutureResultLifetime<'a, T>
+	where
+		Self: Send,
+		T: serde::de::DeserializeOwned + Sized + Send + Sync + 'static,
+	{
+		self.read_deadline(Instant::now() + timeout)
+	}
+}
+
+/// The implementations of [`Read`] which deserialize data from the bytes received over the wire.
+///
+/// These are the default trait methods, and are also used by local streams
+/// when [`serialize_local`](crate::stream::Config::serialize_local) is enabled.
+pub(crate) mod serialized {
+	use super::Read;
+	use crate::{
+		stream::{
+			capture::Event,
+			kind::{code, recv::Error},
+		},
+		utility::PinFutureResultLifetime,
+	};
+
+	pub(crate) fn read_size<'a, R>(stream: &'a mut R) -> PinFutureResultLifetime<'a, usize>
+	where
+		R: Read + Send + ?Sized,
+	{
+		Box::pin(async move {
+			let framing = stream.config().framing;
+			let mut encoded = stream.read_exact(1).await?;
+			let header_len = framing.header_len(encoded[0]);
+			if header_len > 1 {
+				encoded.extend(stream.read_exact(header_len - 1).await?);
+			}
+			Ok(framing.decode(&encoded).map_err(Error::malformed)?)
+		})
+	}
+
+	pub(crate) fn read_bytes<'a, R>(stream: &'a mut R) -> PinFutureResultLifetime<'a, Vec<u8>>
+	where
+		R: Read + Send + ?Sized,
+	{
+		Box::pin(async move {
+			let limit = stream.config().max_message_size;
+			let byte_count = stream.read_size().await?;
+			if byte_count > limit {
+				// The peer cannot be trusted to send anything else of use on this stream.
+				let _ = stream.reject(code::PROTOCOL_VIOLATION).await;
+				return Err(Error::MessageTooLarge {
+					size: byte_count,
+					limit,
+				})?;
+			}
+			let bytes = stream.read_exact(byte_count).await?;
+			if let Some(tap) = &stream.config().capture {
+				let mut framed = stream.config().framing.encode(byte_count)?;
+				framed.extend_from_slice(&bytes);
+				tap.record(Event::Received(framed));
+			}
+			match stream.config().compression {
+				Some(compression) => match compression.decode(bytes, limit) {
+					Ok(bytes) => Ok(bytes),
+					Err(error) => {
+						let _ = stream.reject(code::PROTOCOL_VIOLATION).await;
+						match error.downcast::<Error>() {
+							Ok(error) => Err(error)?,
+							Err(error) => Err(Error::malformed(error))?,
+						}
+					}
+				},
+				None => Ok(bytes),
+			}
+		})
+	}
 }
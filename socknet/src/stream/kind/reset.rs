@@ -0,0 +1,50 @@
+use crate::stream::{kind::code, TimedOut};
+
+/// The error returned by reads and writes once the peer has stopped or reset the stream
+/// with an application error [`code`](code), such as with [`Send::reset`](crate::stream::kind::Send::reset)
+/// or [`Recv::stop`](crate::stream::kind::Recv::stop).
+///
+/// Streams which the peer's handler stopped because it [`timed out`](code::TIMED_OUT)
+/// fail with [`TimedOut::Peer`] instead, and streams which were stopped or reset with a code which
+/// is beyond the range of codes fail with [`UnknownReset`].
+#[derive(thiserror::Error, Clone, Copy, Debug, PartialEq, Eq)]
+#[error("The peer reset the stream with code {code}.")]
+pub struct StreamReset {
+	pub code: u32,
+}
+
+/// The error returned by reads and writes once the peer has stopped or reset a remote stream with a code
+/// which does not fit in a [`code`](code) (so the peer is not a socknet peer).
+#[derive(thiserror::Error, Clone, Copy, Debug, PartialEq, Eq)]
+#[error("The peer reset the stream with unknown code {code}.")]
+pub struct UnknownReset {
+	pub code: u64,
+}
+
+impl StreamReset {
+	/// The code defined by the application, if the stream was stopped or reset with an [`application`](code::application) code.
+	pub fn application(&self) -> Option<u16> {
+		use std::convert::TryFrom;
+		u16::try_from(self.code.checked_sub(code::APPLICATION_START)?).ok()
+	}
+
+	/// Creates the error for a remote stream which the peer stopped or reset with some code.
+	pub(crate) fn from_remote(code: quinn::VarInt) -> anyhow::Error {
+		use std::convert::TryFrom;
+		match u32::try_from(code.into_inner()) {
+			Ok(code) => Self::error(code),
+			Err(_) => UnknownReset {
+				code: code.into_inner(),
+			}
+			.into(),
+		}
+	}
+
+	/// Creates the error for a stream which the peer stopped or reset with some code.
+	pub(crate) fn error(code: u32) -> anyhow::Error {
+		match code {
+			code::TIMED_OUT => TimedOut::Peer.into(),
+			code => Self { code }.into(),
+		}
+	}
+}
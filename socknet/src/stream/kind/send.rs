@@ -13,4 +13,10 @@ pub use datagram::Datagram;
 pub trait Send {
 	/// Finishes the stream.
 	fn finish<'a>(&'a mut self) -> PinFutureResultLifetime<'a, ()>;
+
+	/// Abandons the stream, discarding any unsent data and notifying the peer with an application error
+	/// [`code`](super::code), so it can tell an aborted stream apart from a finished one.
+	/// Codes defined by the application should be created with [`application`](super::code::application).
+	/// The peer's later reads fail with [`StreamReset`](super::StreamReset).
+	fn reset<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()>;
 }
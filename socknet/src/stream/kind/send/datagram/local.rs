@@ -1,14 +1,35 @@
 use crate::{
 	connection::{active::Active, Connection},
 	stream::{
-		kind::send::{Send, Write},
-		local,
+		kind::{
+			send::{write::serialized, Send, Write},
+			Configure,
+		},
+		local, Config,
 	},
 	utility::PinFutureResultLifetime,
 };
 use std::sync::Arc;
 
-pub struct Local(pub(crate) Vec<local::AnyBox>, pub(crate) Arc<Connection>);
+pub struct Local(
+	pub(crate) Vec<local::AnyBox>,
+	pub(crate) Arc<Connection>,
+	pub(crate) Config,
+);
+
+impl Configure for Local {
+	fn config(&self) -> &Config {
+		&self.2
+	}
+
+	/// Local paths never compress their messages, even when they are [`serialized`](Config::serialize_local).
+	fn configure(&mut self, config: &Config) {
+		self.2 = Config {
+			compression: None,
+			..config.clone()
+		};
+	}
+}
 
 impl Write for Local {
 	fn write_exact<'a>(&'a mut self, buf: &'a [u8]) -> PinFutureResultLifetime<'a, ()> {
@@ -19,6 +40,9 @@ impl Write for Local {
 	}
 
 	fn write_size<'a>(&'a mut self, len: usize) -> PinFutureResultLifetime<'a, ()> {
+		if self.2.serialize_local {
+			return serialized::write_size(self, len);
+		}
 		Box::pin(async move {
 			self.0.push(Box::new(len));
 			Ok(())
@@ -26,6 +50,9 @@ impl Write for Local {
 	}
 
 	fn write_bytes<'a>(&'a mut self, data: &'a [u8]) -> PinFutureResultLifetime<'a, ()> {
+		if self.2.serialize_local {
+			return serialized::write_bytes(self, data);
+		}
 		Box::pin(async move {
 			self.0.push(Box::new(data.to_vec()));
 			Ok(())
@@ -38,6 +65,10 @@ impl Write for Local {
 		T: 'static + serde::Serialize + Clone + std::marker::Send + Sync,
 	{
 		Box::pin(async move {
+			if self.2.serialize_local {
+				let codec = self.2.codec;
+				return self.write_with(&codec, data).await;
+			}
 			self.0.push(Box::new(data.clone()));
 			Ok(())
 		})
@@ -52,4 +83,13 @@ impl Send for Local {
 			Ok(())
 		})
 	}
+
+	/// Discards the written data without sending the datagram.
+	/// Nothing has been sent yet, so the peer is not notified.
+	fn reset<'a>(&'a mut self, _code: u32) -> PinFutureResultLifetime<'a, ()> {
+		Box::pin(async move {
+			self.0.clear();
+			Ok(())
+		})
+	}
 }
@@ -1,11 +1,31 @@
 use crate::{
 	connection::{active::Active, Connection},
-	stream::kind::send::{Send, Write},
+	stream::{
+		kind::{
+			send::{Send, Write},
+			Configure,
+		},
+		Config,
+	},
 	utility::PinFutureResultLifetime,
 };
 use std::sync::Arc;
 
-pub struct Remote(pub(crate) Vec<u8>, pub(crate) Arc<Connection>);
+pub struct Remote(
+	pub(crate) Vec<u8>,
+	pub(crate) Arc<Connection>,
+	pub(crate) Config,
+);
+
+impl Configure for Remote {
+	fn config(&self) -> &Config {
+		&self.2
+	}
+
+	fn configure(&mut self, config: &Config) {
+		self.2 = config.clone();
+	}
+}
 
 impl Write for Remote {
 	/// Writes all of the provided bytes to the stream.
@@ -32,4 +52,13 @@ impl Send for Remote {
 			Ok(())
 		})
 	}
+
+	/// Discards the written data without sending the datagram.
+	/// Nothing has been sent yet, so the peer is not notified.
+	fn reset<'a>(&'a mut self, _code: u32) -> PinFutureResultLifetime<'a, ()> {
+		Box::pin(async move {
+			self.0.clear();
+			Ok(())
+		})
+	}
 }
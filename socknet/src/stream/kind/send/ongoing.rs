@@ -8,10 +8,24 @@ pub use remote::Remote;
 pub(crate) mod local;
 pub use local::Local;
 
+mod delivery;
+pub(crate) use delivery::{Delivery, Pace};
+
 /// An outgoing stream that can continue to send data as long as the connection is available.
 /// Once opened, [`write`](super::Write) methods can be used to transmit data.
 pub type Ongoing = Locality<Remote, Local>;
 
+#[cfg_attr(not(feature = "simulator"), allow(dead_code))]
+impl Ongoing {
+	/// Slows down every later write of the stream by the pace, regardless of how the stream is configured.
+	pub(crate) fn set_pace(&mut self, pace: Pace) {
+		match self {
+			Self::Remote(remote) => remote.set_pace(pace),
+			Self::Local(local) => local.set_pace(pace),
+		}
+	}
+}
+
 impl From<quinn::SendStream> for Ongoing {
 	fn from(stream: quinn::SendStream) -> Self {
 		Self::Remote(stream.into())
@@ -0,0 +1,108 @@
+use crate::utility::PinFutureResult;
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::mpsc, task::JoinHandle, time::Instant};
+
+/// How the writes of a stream are slowed down, like they would be on a slower network
+/// (see [`Simulator`](crate::connection::simulator::Simulator)).
+#[derive(Clone)]
+pub(crate) struct Pace {
+	/// Returns how long a write of some number of bytes holds back the writer, while it is sent.
+	pub(crate) transmit: Arc<dyn Fn(usize) -> Duration + Send + Sync + 'static>,
+	/// Returns how long a write takes to arrive once it has been sent.
+	pub(crate) latency: Arc<dyn Fn() -> Duration + Send + Sync + 'static>,
+}
+
+/// Writes an item to the underlying stream.
+pub(crate) type Deliver<T> = Arc<dyn Fn(T) -> PinFutureResult<()> + Send + Sync + 'static>;
+
+enum State<T> {
+	Idle,
+	Forwarding(
+		mpsc::UnboundedSender<(Instant, T)>,
+		JoinHandle<anyhow::Result<()>>,
+	),
+	/// A delivery failed, so later writes are delivered directly (and fail like the stream does).
+	Failed,
+}
+
+/// Delivers the writes of a paced stream once their [`latency`](Pace::latency) has passed,
+/// in the order they were written, without holding back the writer for longer than it takes to send them.
+///
+/// Writes which are pending when the stream is dropped are still delivered.
+pub(crate) struct Delivery<T> {
+	pace: Pace,
+	deliver: Deliver<T>,
+	state: State<T>,
+	/// When the last write arrives, which no later write can arrive before.
+	last: Instant,
+}
+
+impl<T> Delivery<T>
+where
+	T: Send + 'static,
+{
+	pub(crate) fn new(pace: Pace, deliver: Deliver<T>) -> Self {
+		Self {
+			pace,
+			deliver,
+			state: State::Idle,
+			last: Instant::now(),
+		}
+	}
+
+	/// Sends an item of some size in bytes, and queues it until it arrives.
+	/// Fails with the error of an earlier delivery, if one failed.
+	pub(crate) async fn send(&mut self, item: T, size: usize) -> anyhow::Result<()> {
+		tokio::time::sleep((self.pace.transmit)(size)).await;
+		if let State::Failed = self.state {
+			return (self.deliver)(item).await;
+		}
+		self.last = self.last.max(Instant::now() + (self.pace.latency)());
+		if let State::Idle = self.state {
+			let (queue, mut pending) = mpsc::unbounded_channel::<(Instant, T)>();
+			let deliver = self.deliver.clone();
+			let forwarder = tokio::task::spawn(async move {
+				while let Some((arrival, item)) = pending.recv().await {
+					tokio::time::sleep_until(arrival).await;
+					deliver(item).await?;
+				}
+				Ok(())
+			});
+			self.state = State::Forwarding(queue, forwarder);
+		}
+		let failed = match &self.state {
+			State::Forwarding(queue, _) => queue.send((self.last, item)).is_err(),
+			_ => false,
+		};
+		match failed {
+			// The forwarder only stops early if a delivery failed.
+			true => self.flush().await,
+			false => Ok(()),
+		}
+	}
+
+	/// Waits until every pending write has arrived, failing if any of them could not be delivered.
+	pub(crate) async fn flush(&mut self) -> anyhow::Result<()> {
+		match std::mem::replace(&mut self.state, State::Idle) {
+			State::Forwarding(queue, forwarder) => {
+				drop(queue);
+				let delivered = forwarder.await?;
+				if delivered.is_err() {
+					self.state = State::Failed;
+				}
+				delivered
+			}
+			state => {
+				self.state = state;
+				Ok(())
+			}
+		}
+	}
+
+	/// Drops every write which has not arrived yet.
+	pub(crate) fn cancel(&mut self) {
+		if let State::Forwarding(_, forwarder) = std::mem::replace(&mut self.state, State::Idle) {
+			forwarder.abort();
+		}
+	}
+}
@@ -1,43 +1,153 @@
 use crate::{
 	stream::{
-		kind::send::{Send, Write},
-		local,
+		kind::{
+			code,
+			send::{
+				ongoing::{Delivery, Pace},
+				write::serialized,
+				Send, Write,
+			},
+			Configure, StreamReset,
+		},
+		local, Config, Deadline,
 	},
 	utility::PinFutureResultLifetime,
 };
 
-pub(crate) type Internal = async_channel::Sender<local::AnyBox>;
-pub struct Local(Internal);
+/// The channel backing a local stream, the signal for when its connection is closed,
+/// and the codes the stream was stopped or reset with.
+pub(crate) struct Internal(
+	pub(crate) async_channel::Sender<local::AnyBox>,
+	pub(crate) local::Closed,
+	pub(crate) local::Codes,
+);
+pub struct Local(
+	async_channel::Sender<local::AnyBox>,
+	Config,
+	local::Closed,
+	local::Codes,
+	Option<Delivery<local::AnyBox>>,
+);
 
 impl From<Internal> for Local {
-	fn from(stream: Internal) -> Self {
-		Self(stream)
+	fn from(Internal(stream, closed, codes): Internal) -> Self {
+		Self(stream, Config::default(), closed, codes, None)
+	}
+}
+
+impl Configure for Local {
+	fn config(&self) -> &Config {
+		&self.1
+	}
+
+	/// Local paths never compress their messages, even when they are [`serialized`](Config::serialize_local).
+	///
+	/// The stream is reset with [`TIMED_OUT`](code::TIMED_OUT) once its [`deadline`](Config::deadline) expires.
+	fn configure(&mut self, config: &Config) {
+		if let Some(deadline) = Deadline::added(&self.1.deadline, &config.deadline) {
+			let codes = self.3.clone();
+			deadline.on_expired(move || codes.reset(code::TIMED_OUT));
+		}
+		self.1 = Config {
+			compression: None,
+			..config.clone()
+		};
 	}
 }
 
 impl Local {
-	fn write_any<'a, T>(&'a mut self, any: T) -> PinFutureResultLifetime<'a, ()>
+	#[cfg_attr(not(feature = "simulator"), allow(dead_code))]
+	pub(crate) fn set_pace(&mut self, pace: Pace) {
+		let (stream, closed, codes) = (self.0.clone(), self.2.clone(), self.3.clone());
+		self.4 = Some(Delivery::new(
+			pace,
+			std::sync::Arc::new(move |item| {
+				Box::pin(deliver(stream.clone(), closed.clone(), codes.clone(), item))
+			}),
+		));
+	}
+
+	/// Resets the stream with a code, so the reader fails instead of reading any more items.
+	fn end(&mut self, code: u32) {
+		if let Some(delivery) = &mut self.4 {
+			delivery.cancel();
+		}
+		self.3.reset(code);
+		self.0.close();
+	}
+
+	/// Sends an item. If the stream is [`paced`](Pace), the item is sent once it arrives,
+	/// and the write only waits until its size in bytes (which is only known for serialized items) has been sent.
+	fn write_any<'a, T>(&'a mut self, any: T, size: usize) -> PinFutureResultLifetime<'a, ()>
 	where
 		T: std::marker::Send + Sync + 'static,
 	{
 		Box::pin(async move {
-			self.0.send(Box::new(any)).await?;
-			Ok(())
+			if let Some(error) = self.2.error() {
+				return Err(error)?;
+			}
+			if let Some(code) = self.3.stopped() {
+				return Err(StreamReset::error(code));
+			}
+			let item: local::AnyBox = Box::new(any);
+			let send: PinFutureResultLifetime<'_, ()> = match &mut self.4 {
+				Some(delivery) => Box::pin(delivery.send(item, size)),
+				None => Box::pin(deliver(
+					self.0.clone(),
+					self.2.clone(),
+					self.3.clone(),
+					item,
+				)),
+			};
+			match &self.1.deadline {
+				Some(deadline) => match deadline.bound(send).await {
+					Ok(sent) => sent,
+					Err(expired) => {
+						self.end(code::TIMED_OUT);
+						Err(expired)?
+					}
+				},
+				None => send.await,
+			}
 		})
 	}
 }
 
+/// Sends an item to the reader, failing if the stream is stopped or its connection is closed first.
+async fn deliver(
+	stream: async_channel::Sender<local::AnyBox>,
+	mut closed: local::Closed,
+	codes: local::Codes,
+	item: local::AnyBox,
+) -> anyhow::Result<()> {
+	tokio::select! {
+		result = stream.send(item) => match (result, codes.stopped()) {
+			// The reader stopped the stream while this was waiting.
+			(Err(_), Some(code)) => Err(StreamReset::error(code)),
+			(result, _) => Ok(result?),
+		},
+		code = codes.wait_stopped() => Err(StreamReset::error(code)),
+		error = closed.wait() => Err(error)?,
+	}
+}
+
 impl Write for Local {
 	fn write_exact<'a>(&'a mut self, buf: &'a [u8]) -> PinFutureResultLifetime<'a, ()> {
-		self.write_any(buf.to_vec())
+		self.write_any(buf.to_vec(), buf.len())
 	}
 
 	fn write_size<'a>(&'a mut self, len: usize) -> PinFutureResultLifetime<'a, ()> {
-		self.write_any(len)
+		match self.1.serialize_local {
+			true => serialized::write_size(self, len),
+			false => self.write_any(len, 0),
+		}
 	}
 
 	fn write_bytes<'a>(&'a mut self, data: &'a [u8]) -> PinFutureResultLifetime<'a, ()> {
-		self.write_any(data.to_vec())
+		match self.1.serialize_local {
+			true => serialized::write_bytes(self, data),
+			false => self.write_any(data.to_vec(), 0),
+		}
 	}
 
 	fn write<'a, T>(&'a mut self, data: &'a T) -> PinFutureResultLifetime<'a, ()>
@@ -45,12 +155,41 @@ impl Write for Local {
 		Self: std::marker::Send,
 		T: 'static + serde::Serialize + Clone + std::marker::Send + Sync,
 	{
-		self.write_any(data.clone())
+		match self.1.serialize_local {
+			true => Box::pin(async move {
+				let codec = self.1.codec;
+				self.write_with(&codec, data).await
+			}),
+			false => self.write_any(data.clone(), 0),
+		}
+	}
+
+	/// Resets the stream, so the reader's later reads fail with [`StreamReset`] (like a remote stream).
+	fn abort<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()> {
+		self.reset(code)
 	}
 }
 
 impl Send for Local {
+	/// Waits until all of the stream's [`paced`](Pace) writes have arrived,
+	/// and fails with [`StreamReset`] if the reader stopped the stream (like a remote stream).
 	fn finish<'a>(&'a mut self) -> PinFutureResultLifetime<'a, ()> {
-		Box::pin(async move { Ok(()) })
+		Box::pin(async move {
+			if let Some(delivery) = &mut self.4 {
+				delivery.flush().await?;
+			}
+			match self.3.stopped() {
+				Some(code) => Err(StreamReset::error(code)),
+				None => Ok(()),
+			}
+		})
+	}
+
+	/// Resets the stream, so the reader's later reads fail with [`StreamReset`] (like a remote stream).
+	fn reset<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()> {
+		Box::pin(async move {
+			self.end(code);
+			Ok(())
+		})
 	}
 }
@@ -1,38 +1,160 @@
 use crate::{
-	stream::kind::send::{Send, Write},
+	stream::{
+		kind::{
+			code,
+			send::{
+				ongoing::{Delivery, Pace},
+				Send, Write,
+			},
+			Configure, StreamReset,
+		},
+		Config, Deadline,
+	},
 	utility::PinFutureResultLifetime,
 };
+use std::sync::Arc;
 
-pub struct Remote(quinn::SendStream);
+/// The stream is shared with its [`deadline`](Config::deadline), which resets the stream once it expires.
+pub struct Remote(
+	Arc<tokio::sync::Mutex<quinn::SendStream>>,
+	Config,
+	Option<Delivery<Vec<u8>>>,
+);
+
+/// Converts the error of a write, so that the peer stopping the stream is a [`StreamReset`] error.
+fn write_error(error: quinn::WriteError) -> anyhow::Error {
+	match error {
+		quinn::WriteError::Stopped(code) => StreamReset::from_remote(code),
+		error => error.into(),
+	}
+}
 
 impl From<quinn::SendStream> for Remote {
 	fn from(stream: quinn::SendStream) -> Self {
-		Self(stream)
+		Self(
+			Arc::new(tokio::sync::Mutex::new(stream)),
+			Config::default(),
+			None,
+		)
+	}
+}
+
+#[cfg_attr(not(feature = "simulator"), allow(dead_code))]
+impl Remote {
+	pub(crate) fn set_pace(&mut self, pace: Pace) {
+		let stream = self.0.clone();
+		self.2 = Some(Delivery::new(
+			pace,
+			std::sync::Arc::new(move |bytes: Vec<u8>| {
+				let stream = stream.clone();
+				Box::pin(async move {
+					stream
+						.lock()
+						.await
+						.write_all(&bytes)
+						.await
+						.map_err(write_error)
+				})
+			}),
+		));
+	}
+}
+
+impl Configure for Remote {
+	fn config(&self) -> &Config {
+		&self.1
+	}
+
+	fn configure(&mut self, config: &Config) {
+		if let Some(deadline) = Deadline::added(&self.1.deadline, &config.deadline) {
+			let stream = Arc::downgrade(&self.0);
+			deadline.on_expired(move || {
+				// A stream which is in use is reset by the operation, which is bounded by the same deadline.
+				if let Some(Ok(mut stream)) =
+					stream.upgrade().as_ref().map(|stream| stream.try_lock())
+				{
+					let _ = stream.reset(quinn::VarInt::from_u32(code::TIMED_OUT));
+				}
+			});
+		}
+		self.1 = config.clone();
 	}
 }
 
 impl Write for Remote {
-	/// Writes all of the provided bytes to the stream.
+	/// Writes all of the provided bytes to the stream. If the stream is [`paced`](Pace),
+	/// the bytes are written once they arrive, and the write only waits until they have been sent.
+	/// If the stream has a [`deadline`](Config::deadline) which expires first,
+	/// the stream is reset with [`TIMED_OUT`](code::TIMED_OUT).
 	///
 	/// Mirrors [`read_exact`](crate::stream::kind::Read::read_exact).
 	///
 	/// See [`quinn`](quinn::SendStream::write_all) for more details.
 	fn write_exact<'a>(&'a mut self, buf: &'a [u8]) -> PinFutureResultLifetime<'a, ()> {
 		Box::pin(async move {
-			self.0.write_all(&buf).await?;
-			Ok(())
+			let deadline = self.1.deadline.clone();
+			let (stream, delivery) = (&self.0, &mut self.2);
+			let write = async move {
+				match delivery {
+					Some(delivery) => delivery.send(buf.to_vec(), buf.len()).await,
+					None => stream
+						.lock()
+						.await
+						.write_all(buf)
+						.await
+						.map_err(write_error),
+				}
+			};
+			match deadline {
+				Some(deadline) => match deadline.bound(write).await {
+					Ok(written) => written,
+					Err(expired) => {
+						if let Some(delivery) = &mut self.2 {
+							delivery.cancel();
+						}
+						let _ = self
+							.0
+							.lock()
+							.await
+							.reset(quinn::VarInt::from_u32(code::TIMED_OUT));
+						Err(expired)?
+					}
+				},
+				None => write.await,
+			}
 		})
 	}
+
+	/// Resets the stream, notifying the peer of the provided code.
+	///
+	/// See [`quinn`](quinn::SendStream::reset) for more details.
+	fn abort<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()> {
+		self.reset(code)
+	}
 }
 
 impl Send for Remote {
-	/// Finishes the stream.
+	/// Finishes the stream, once all of its [`paced`](Pace) writes have arrived.
 	///
 	/// See [`quinn`](quinn::SendStream::finish) for more details.
 	fn finish<'a>(&'a mut self) -> PinFutureResultLifetime<'a, ()> {
 		Box::pin(async move {
-			self.0.finish().await?;
-			Ok(())
+			if let Some(delivery) = &mut self.2 {
+				delivery.flush().await?;
+			}
+			self.0.lock().await.finish().await.map_err(write_error)
+		})
+	}
+
+	/// Resets the stream, notifying the peer of the provided code.
+	///
+	/// See [`quinn`](quinn::SendStream::reset) for more details.
+	fn reset<'a>(&'a mut self, code: u32) -> PinFutureResultLifetime<'a, ()> {
+		Box::pin(async move {
+			if let Some(delivery) = &mut self.2 {
+				delivery.cancel();
+			}
+			Ok(self.0.lock().await.reset(quinn::VarInt::from_u32(code))?)
 		})
 	}
 }
@@ -1,13 +1,23 @@
-use crate::utility::PinFutureResultLifetime;
+use crate::{
+	stream::{
+		codec::Codec,
+		kind::{code, Configure},
+		TimedOut,
+	},
+	utility::PinFutureResultLifetime,
+};
+use std::time::Duration;
+use tokio::time::Instant;
 
 /// Local interface for providing the async-write functionality to streams.
-pub trait Write {
+pub trait Write: Configure {
 	/// Writes all of the provided bytes to the stream.
 	///
 	/// Mirrors [`read_exact`](crate::stream::kind::Read::read_exact).
 	fn write_exact<'a>(&'a mut self, buf: &'a [u8]) -> PinFutureResultLifetime<'a, ()>;
 
-	/// Writes a usize to the stream, encoded at a specific byte length.
+	/// Writes a usize to the stream, encoded by the [`framing`](crate::stream::Config::framing) of the stream.
+	/// Fails if the size cannot be encoded by the framing.
 	///
 	/// Used primarily internally to write the sizes of byte vecs and generic structs to the stream.
 	///
@@ -16,15 +26,12 @@ pub trait Write {
 	where
 		Self: Send,
 	{
-		Box::pin(async move {
-			let len = len as u32;
-			let len_encoded = bincode::serialize(&len)?;
-			self.write_exact(&len_encoded).await?;
-			Ok(())
-		})
+		serialized::write_size(self, len)
 	}
 
 	/// Writes a set of bytes as a distinct vec, prefixing it with a size header.
+	/// If [`compression`](crate::stream::Config::compression) is configured for the stream,
+	/// the bytes are prefixed by a compression header and may be compressed.
 	///
 	/// This is different than [`write_exact`](Self::write_exact) because it provides a distinct header between set of bytes.
 	///
@@ -33,26 +40,145 @@ pub trait Write {
 	where
 		Self: Send,
 	{
-		Box::pin(async move {
-			self.write_size(data.len()).await?;
-			self.write_exact(data).await?;
-			Ok(())
-		})
+		serialized::write_bytes(self, data)
+	}
+
+	/// Stops writing to the stream because a message could not be completed,
+	/// notifying the peer (where possible) with the provided [`code`](crate::stream::kind::code).
+	///
+	/// Does nothing by default, which is the case for streams that are only sent once they are finished (like datagrams).
+	///
+	/// Mirrors [`reject`](crate::stream::kind::Read::reject).
+	fn abort<'a>(&'a mut self, _code: u32) -> PinFutureResultLifetime<'a, ()> {
+		Box::pin(async move { Ok(()) })
 	}
 
 	/// Writes some generic sized data to the stream, prefixing it with a size header.
+	/// The data is encoded using the [`codec`](crate::stream::Config::codec) configured for the stream.
 	///
 	/// Mirrors [`read`](crate::stream::kind::Read::read).
 	fn write<'a, T>(&'a mut self, data: &'a T) -> PinFutureResultLifetime<'a, ()>
 	where
 		Self: Send,
 		T: 'static + serde::Serialize + Clone + Send + Sync,
+	{
+		Box::pin(async move {
+			let codec = self.config().codec;
+			self.write_with(&codec, data).await
+		})
+	}
+
+	/// Writes some generic sized data to the stream, prefixing it with a size header.
+	/// The data is encoded using the provided codec.
+	///
+	/// Mirrors [`read_with`](crate::stream::kind::Read::read_with).
+	fn write_with<'a, C, T>(
+		&'a mut self,
+		codec: &'a C,
+		data: &'a T,
+	) -> PinFutureResultLifetime<'a, ()>
+	where
+		Self: Send,
+		C: Codec + Sync,
+		T: 'static + serde::Serialize + Clone + Send + Sync,
 	{
 		Box::pin(async move {
 			// The data to be sent, in serialized to bytes
-			let data_encoded = bincode::serialize(&data)?;
+			let data_encoded = codec.encode(data)?;
 			self.write_bytes(&data_encoded).await?;
 			Ok(())
 		})
 	}
+
+	/// Writes some generic sized data like [`write`](Self::write), unless the deadline passes first.
+	///
+	/// A write which does not complete in time may have sent part of a message,
+	/// so the stream is [`aborted`](Self::abort) with [`TIMED_OUT`](code::TIMED_OUT)
+	/// and [`TimedOut::Deadline`] is returned. Any later writes fail.
+	/// Datagrams are written to memory, so their writes always complete before the deadline.
+	///
+	/// Mirrors [`read_deadline`](crate::stream::kind::Read::read_deadline).
+	fn write_deadline<'a, T>(
+		&'a mut self,
+		data: &'a T,
+		deadline: Instant,
+	) -> PinFutureResultLifetime<'a, ()>
+	where
+		Self: Send,
+		T: 'static + serde::Serialize + Clone + Send + Sync,
+	{
+		Box::pin(async move {
+			let written = tokio::time::timeout_at(deadline, self.write(data)).await;
+			match written {
+				Ok(written) => written,
+				Err(_) => {
+					let _ = self.abort(code::TIMED_OUT).await;
+					Err(TimedOut::Deadline)?
+				}
+			}
+		})
+	}
+
+	/// Writes some generic sized data like [`write`](Self::write), unless it takes longer than the timeout.
+	/// See [`write_deadline`](Self::write_deadline) for the state of the stream if it times out.
+	///
+	/// Mirrors [`read_timeout`](crate::stream::kind::Read::read_timeout).
+	fn write_timeout<'a, T>(
+		&'a mut self,
+		data: &'a T,
+		timeout: Duration,
+	) -> PinFutureResultLifetime<'a, ()>
+	where
+		Self: Send,
+		T: 'static + serde::Serialize + Clone + Send + Sync,
+	{
+		self.write_deadline(data, Instant::now() + timeout)
+	}
+}
+
+/// The implementations of [`Write`] which serialize data to the bytes sent over the wire.
+///
+/// These are the default trait methods, and are also used by local streams
+/// when [`serialize_local`](crate::stream::Config::serialize_local) is enabled.
+pub(crate) mod serialized {
+	use super::Write;
+	use crate::{stream::capture::Event, utility::PinFutureResultLifetime};
+
+	pub(crate) fn write_size<'a, W>(
+		stream: &'a mut W,
+		len: usize,
+	) -> PinFutureResultLifetime<'a, ()>
+	where
+		W: Write + Send + ?Sized,
+	{
+		Box::pin(async move {
+			let len_encoded = stream.config().framing.encode(len)?;
+			stream.write_exact(&len_encoded).await?;
+			Ok(())
+		})
+	}
+
+	pub(crate) fn write_bytes<'a, W>(
+		stream: &'a mut W,
+		data: &'a [u8],
+	) -> PinFutureResultLifetime<'a, ()>
+	where
+		W: Write + Send + ?Sized,
+	{
+		Box::pin(async move {
+			let compressed = match stream.config().compression {
+				Some(compression) => Some(compression.encode(data)?),
+				None => None,
+			};
+			let message = compressed.as_deref().unwrap_or(data);
+			if let Some(tap) = &stream.config().capture {
+				let mut framed = stream.config().framing.encode(message.len())?;
+				framed.extend_from_slice(message);
+				tap.record(Event::Sent(framed));
+			}
+			stream.write_size(message.len()).await?;
+			stream.write_exact(message).await?;
+			Ok(())
+		})
+	}
 }
@@ -0,0 +1,220 @@
+use crate::stream::{Completion, Deadline};
+use std::{
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc, Mutex,
+	},
+	time::Duration,
+};
+use tokio::{
+	sync::{OwnedSemaphorePermit, Semaphore},
+	time::Instant,
+};
+
+/// What happens to an incoming stream (or datagram) which exceeds its [`Limits`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+	/// Waits until the stream is within the limits before handing it to its receiver.
+	/// Once [`max_queued`](Limits::max_queued) streams are waiting, later streams are handled like [`Reset`](Self::Reset).
+	Queue,
+	/// Discards the stream, so the peer only sees that it was stopped (without a reason).
+	Drop,
+	/// [`Rejects`](crate::stream::kind::Kind::reject) the stream with [`LIMIT_EXCEEDED`](crate::stream::kind::code::LIMIT_EXCEEDED).
+	/// Datagrams are discarded, because there is no peer to notify.
+	Reset,
+}
+
+/// A token bucket, which allows `burst` streams at once, and is refilled by `per_second` streams every second.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rate {
+	pub per_second: f64,
+	pub burst: u32,
+}
+
+/// Limits on the incoming streams (and datagrams) which are handed to receivers.
+///
+/// Limits can be applied to a single handler (see [`Registration::limit`](crate::stream::Registration::limit)),
+/// or to all of the handlers of each connection (see [`Registry::limit_connections`](crate::stream::Registry::limit_connections)).
+/// Streams which exceed the limits are handled according to the [`overflow`](Self::overflow) policy,
+/// and are reported to the endpoint as a [`Limited`](crate::connection::event::Event::Limited) event.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+	/// The maximum number of receivers which can be active at once.
+	/// A receiver is active until its [`context`](crate::stream::Context) (which holds its [`Permit`]) is dropped.
+	pub max_receivers: Option<usize>,
+	/// The rate at which new streams can be handed to receivers.
+	pub rate: Option<Rate>,
+	/// The maximum number of streams which can wait for the limits, if the [`overflow`](Self::overflow) policy is to
+	/// [`Queue`](Overflow::Queue) them.
+	pub max_queued: usize,
+	pub overflow: Overflow,
+}
+
+impl Limits {
+	pub const DEFAULT_MAX_QUEUED: usize = 64;
+}
+
+impl Default for Limits {
+	fn default() -> Self {
+		Self {
+			max_receivers: None,
+			rate: None,
+			max_queued: Self::DEFAULT_MAX_QUEUED,
+			overflow: Overflow::Queue,
+		}
+	}
+}
+
+/// Marks a receiver as active for the [`max_receivers`](Limits::max_receivers) of its limits,
+/// until the permit is dropped along with the receiver's [`context`](crate::stream::Context),
+/// which also [`completes`](crate::stream::Interceptor::completed) the interceptors of the stream.
+#[derive(Default)]
+pub struct Permit(Vec<OwnedSemaphorePermit>, Option<Completion>);
+
+impl Permit {
+	pub(crate) fn join(&mut self, mut other: Permit) {
+		self.0.append(&mut other.0);
+		if let Some(completion) = other.1.take() {
+			self.1 = Some(completion);
+		}
+	}
+
+	pub(crate) fn complete_with(&mut self, completion: Completion) {
+		self.1 = Some(completion);
+	}
+
+	pub(crate) fn set_deadline(&mut self, deadline: Deadline) {
+		if let Some(completion) = &mut self.1 {
+			completion.set_deadline(deadline);
+		}
+	}
+}
+
+impl Drop for Permit {
+	fn drop(&mut self) {
+		// The receiver is no longer active by the time the interceptors are completed.
+		self.0.clear();
+		if let Some(completion) = self.1.take() {
+			completion.receiver_dropped();
+		}
+	}
+}
+
+/// A stream waiting in the queue of a [`Limiter`], which leaves the queue when this is dropped.
+pub(crate) struct Queued(Arc<AtomicUsize>);
+
+impl Drop for Queued {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
+/// How a stream which was checked against a [`Limiter`] may continue.
+pub(crate) enum Admission {
+	/// The stream is within the limits.
+	Admitted(Permit),
+	/// The stream must wait until it is within the limits (see [`admit`](Limiter::admit)).
+	Queued(Queued),
+}
+
+struct Bucket {
+	tokens: f64,
+	updated: Instant,
+}
+
+/// Enforces a set of [`Limits`].
+pub(crate) struct Limiter {
+	limits: Limits,
+	receivers: Option<Arc<Semaphore>>,
+	bucket: Option<Mutex<Bucket>>,
+	/// The number of streams which are waiting for the limits.
+	queued: Arc<AtomicUsize>,
+}
+
+impl From<Limits> for Limiter {
+	fn from(limits: Limits) -> Self {
+		Self {
+			limits,
+			receivers: limits
+				.max_receivers
+				.map(|count| Arc::new(Semaphore::new(count))),
+			bucket: limits.rate.map(|rate| {
+				Mutex::new(Bucket {
+					tokens: rate.burst as f64,
+					updated: Instant::now(),
+				})
+			}),
+			queued: Arc::new(AtomicUsize::new(0)),
+		}
+	}
+}
+
+impl Limiter {
+	/// Admits a stream if it is within the limits, without waiting.
+	fn try_admit(&self) -> Option<Permit> {
+		let permit = match &self.receivers {
+			Some(receivers) => Some(receivers.clone().try_acquire_owned().ok()?),
+			None => None,
+		};
+		self.take_token().ok()?;
+		Some(Permit(permit.into_iter().collect(), None))
+	}
+
+	/// Admits a stream if it is within the limits, or places it in the queue if the overflow policy is to queue streams
+	/// (and the queue is not full), without waiting.
+	/// Otherwise returns the overflow policy to apply to the stream.
+	pub(crate) fn check(&self) -> Result<Admission, Overflow> {
+		if let Some(permit) = self.try_admit() {
+			return Ok(Admission::Admitted(permit));
+		}
+		if self.limits.overflow != Overflow::Queue {
+			return Err(self.limits.overflow);
+		}
+		let max_queued = self.limits.max_queued;
+		match self
+			.queued
+			.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |queued| {
+				(queued < max_queued).then(|| queued + 1)
+			}) {
+			Ok(_) => Ok(Admission::Queued(Queued(self.queued.clone()))),
+			Err(_) => Err(Overflow::Reset),
+		}
+	}
+
+	/// Waits until a stream is within the limits, and then admits it.
+	pub(crate) async fn admit(&self) -> Permit {
+		let permit = match &self.receivers {
+			// The semaphore is never closed.
+			Some(receivers) => receivers.clone().acquire_owned().await.ok(),
+			None => None,
+		};
+		while let Err(wait) = self.take_token() {
+			tokio::time::sleep(wait).await;
+		}
+		Permit(permit.into_iter().collect(), None)
+	}
+
+	/// Takes a token from the bucket, or returns how long it will be until a token is available.
+	fn take_token(&self) -> Result<(), Duration> {
+		let (bucket, rate) = match (&self.bucket, self.limits.rate) {
+			(Some(bucket), Some(rate)) => (bucket, rate),
+			_ => return Ok(()),
+		};
+		let mut bucket = bucket.lock().unwrap();
+		let now = Instant::now();
+		let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+		bucket.tokens = (bucket.tokens + elapsed * rate.per_second).min(rate.burst as f64);
+		bucket.updated = now;
+		if bucket.tokens >= 1.0 {
+			bucket.tokens -= 1.0;
+			return Ok(());
+		}
+		if rate.per_second <= 0.0 {
+			// The bucket never refills, so the stream waits until its task is dropped with the connection.
+			return Err(Duration::from_secs(1));
+		}
+		Err(Duration::from_secs_f64(
+			(1.0 - bucket.tokens) / rate.per_second,
+		))
+	}
+}
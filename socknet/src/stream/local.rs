@@ -1,4 +1,166 @@
-pub type AnyBox = Box<dyn std::any::Any + std::marker::Send + Sync + 'static>;
+use crate::{connection, stream::kind::recv::Error};
+use std::{
+	any::Any,
+	sync::{Arc, Mutex},
+};
+
+/// A value which is sent over a local stream as-is, without being serialized.
+///
+/// Implemented for all thread-safe types, and remembers the name of the value's type,
+/// so that reading a value of an unexpected type can report what was actually written.
+pub trait Value: Any + Send + Sync {
+	/// The name of the value's type, as provided by [`type_name`](std::any::type_name).
+	fn type_name(&self) -> &'static str;
+
+	fn as_any(&self) -> &(dyn Any + Send + Sync);
+
+	fn into_any(self: Box<Self>) -> Box<dyn Any + Send + Sync>;
+}
+
+impl<T> Value for T
+where
+	T: Any + Send + Sync,
+{
+	fn type_name(&self) -> &'static str {
+		std::any::type_name::<T>()
+	}
+
+	fn as_any(&self) -> &(dyn Any + Send + Sync) {
+		self
+	}
+
+	fn into_any(self: Box<Self>) -> Box<dyn Any + Send + Sync> {
+		self
+	}
+}
+
+pub type AnyBox = Box<dyn Value>;
 #[allow(dead_code)]
-pub type Incoming<T> = async_channel::Receiver<Result<T, quinn::ConnectionError>>;
-pub type Outgoing<T> = async_channel::Sender<Result<T, quinn::ConnectionError>>;
+pub(crate) type Incoming<T> = async_channel::Receiver<Result<T, quinn::ConnectionError>>;
+pub(crate) type Outgoing<T> = async_channel::Sender<Result<T, quinn::ConnectionError>>;
+
+/// The code and reason a local connection was closed with, if it has been closed.
+pub(crate) type CloseState = Option<(connection::CloseCode, String)>;
+
+/// Notifies the streams of a local connection when the connection is [`closed`](connection::Active::close),
+/// so that pending reads and writes end like they would for a remote connection.
+#[derive(Clone)]
+pub(crate) struct Closed(tokio::sync::watch::Receiver<CloseState>);
+
+impl From<tokio::sync::watch::Receiver<CloseState>> for Closed {
+	fn from(receiver: tokio::sync::watch::Receiver<CloseState>) -> Self {
+		Self(receiver)
+	}
+}
+
+impl Closed {
+	/// Returns the [`ConnectionClosed`](connection::Error::ConnectionClosed) error if the connection has been closed.
+	pub(crate) fn error(&self) -> Option<connection::Error> {
+		closed_error(&self.0.borrow())
+	}
+
+	/// Waits until the connection is closed, returning the [`ConnectionClosed`](connection::Error::ConnectionClosed) error.
+	/// Never completes if the connection is dropped without being closed.
+	pub(crate) async fn wait(&mut self) -> connection::Error {
+		loop {
+			if let Some(error) = self.error() {
+				return error;
+			}
+			if self.0.changed().await.is_err() {
+				futures::future::pending::<()>().await;
+			}
+		}
+	}
+}
+
+pub(crate) fn closed_error(state: &CloseState) -> Option<connection::Error> {
+	state
+		.as_ref()
+		.map(|(code, reason)| connection::Error::ConnectionClosed {
+			code: *code,
+			reason: reason.clone(),
+		})
+}
+
+#[derive(Default)]
+struct CodesState {
+	stopped: Option<u32>,
+	reset: Option<u32>,
+}
+
+/// The codes which a local stream was [`stopped`](crate::stream::kind::Recv::stop) by its reader
+/// or [`reset`](crate::stream::kind::Send::reset) by its writer with, shared by both ends of the stream.
+#[derive(Clone, Default)]
+pub(crate) struct Codes(Arc<Mutex<CodesState>>, Arc<tokio::sync::Notify>);
+
+impl Codes {
+	pub(crate) fn stop(&self, code: u32) {
+		self.0.lock().unwrap().stopped.get_or_insert(code);
+		self.1.notify_waiters();
+	}
+
+	pub(crate) fn reset(&self, code: u32) {
+		self.0.lock().unwrap().reset.get_or_insert(code);
+		self.1.notify_waiters();
+	}
+
+	/// Waits until the stream is stopped, returning its code.
+	pub(crate) async fn wait_stopped(&self) -> u32 {
+		loop {
+			let changed = self.1.notified();
+			if let Some(code) = self.stopped() {
+				return code;
+			}
+			changed.await;
+		}
+	}
+
+	/// Waits until the stream is reset, returning its code.
+	pub(crate) async fn wait_reset(&self) -> u32 {
+		loop {
+			let changed = self.1.notified();
+			if let Some(code) = self.was_reset() {
+				return code;
+			}
+			changed.await;
+		}
+	}
+
+	pub(crate) fn stopped(&self) -> Option<u32> {
+		self.0.lock().unwrap().stopped
+	}
+
+	pub(crate) fn was_reset(&self) -> Option<u32> {
+		self.0.lock().unwrap().reset
+	}
+}
+
+/// Extracts the value of a specific type from a local stream item,
+/// failing with [`TypeMismatch`](Error::TypeMismatch) if the item has a different type.
+pub(crate) fn downcast<T>(item: AnyBox) -> Result<T, Error>
+where
+	T: Any,
+{
+	// `Value` is implemented for `Box<dyn Value>` too, so the trait method must be called on the inner value.
+	let actual = Value::type_name(item.as_ref());
+	match item.into_any().downcast::<T>() {
+		Ok(value) => Ok(*value),
+		Err(_) => Err(Error::TypeMismatch {
+			expected: std::any::type_name::<T>(),
+			actual,
+		}),
+	}
+}
+
+/// Extracts a byte buffer of a specific length from a local stream item,
+/// failing with [`LengthMismatch`](Error::LengthMismatch) if the buffer has a different length.
+pub(crate) fn downcast_exact(item: AnyBox, byte_count: usize) -> Result<Vec<u8>, Error> {
+	let bytes = downcast::<Vec<u8>>(item)?;
+	if bytes.len() != byte_count {
+		return Err(Error::LengthMismatch {
+			expected: byte_count,
+			actual: bytes.len(),
+		});
+	}
+	Ok(bytes)
+}
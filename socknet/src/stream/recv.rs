@@ -22,6 +22,12 @@ pub trait AppContext {
 	/// when a stream with its [`unique_id`](stream::Identifier::unique_id) is encountered.
 	type Receiver: stream::handler::Receiver;
 
+	/// Adjusts the endpoint's [`stream configuration`](stream::Config) for streams received by this builder.
+	/// The configuration is applied after the handler id has been read from the stream.
+	///
+	/// Does nothing by default, so streams use the configuration of the endpoint.
+	fn configure(&self, _config: &mut stream::Config) {}
+
 	/// Extracts the stream using the [`Extractor`](Self::Extractor) associated type,
 	/// then wraps the context, connection, and extracted stream into a [`context`](stream::recv::Context).
 	fn into_context(
@@ -37,6 +43,7 @@ pub trait AppContext {
 			builder: self,
 			connection,
 			stream,
+			permit: stream::Permit::default(),
 		})
 	}
 
@@ -1,13 +1,34 @@
-use crate::{connection::Connection, stream};
+use crate::{connection::Connection, endpoint::Role, stream};
 use anyhow::Context;
-use std::{collections::HashMap, sync::Arc};
+use std::{
+	collections::HashMap,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc, RwLock,
+	},
+};
+use tokio::sync::broadcast;
 
 type AnyArc = Arc<dyn std::any::Any + Send + Sync + 'static>;
+type Authorize = Box<dyn Fn(&Connection) -> bool + Send + Sync + 'static>;
 struct Registered {
 	identifier: AnyArc,
 	fn_process: Box<
-		dyn Fn(Arc<Connection>, stream::kind::Kind) -> anyhow::Result<()> + Send + Sync + 'static,
+		dyn Fn(Arc<Connection>, stream::kind::Kind, stream::Permit) -> anyhow::Result<()>
+			+ Send
+			+ Sync
+			+ 'static,
 	>,
+	/// Decides if a connection may use the handler, which all connections can if there is no predicate.
+	authorize: Option<Authorize>,
+	/// The number of streams which were rejected because their connection was not authorized.
+	denials: AtomicU64,
+	/// The role of the peers which may open streams for the handler, which any peer can if there is no role.
+	origin: Option<Role>,
+	/// Limits the receivers of the handler across all connections, which are unlimited if there is no limiter.
+	limiter: Option<stream::Limiter>,
+	/// How long each receiver of the handler may take, which is unbounded if there is no timeout.
+	timeout: Option<stream::Timeout>,
 }
 impl<T> From<T> for Registered
 where
@@ -21,56 +42,230 @@ where
 		let recv_builder = other.recv_builder().clone();
 		Self {
 			identifier: Arc::new(other),
-			fn_process: Box::new(move |connection, stream| {
-				use stream::recv::AppContext;
+			fn_process: Box::new(move |connection, mut stream, permit| {
+				use stream::{kind::Configure, recv::AppContext};
 				let builder = recv_builder.clone();
-				let context = builder.into_context(connection, stream)?;
+				let mut config = connection.stream_config()?;
+				builder.configure(&mut config);
+				config.capture = stream.config().capture.clone();
+				config.deadline = stream.config().deadline.clone();
+				stream.configure(&config);
+				let mut context = builder.into_context(connection, stream)?;
+				context.permit = permit;
 				<T as stream::Identifier>::RecvBuilder::process(context);
 				Ok(())
 			}),
+			authorize: None,
+			denials: AtomicU64::new(0),
+			origin: None,
+			limiter: None,
+			timeout: None,
 		}
 	}
 }
 impl Registered {
+	fn accepts_from(&self, connection: &Connection) -> bool {
+		match self.origin {
+			// An endpoint connected to itself plays both roles, so it can use all of its handlers.
+			Some(origin) => connection.is_to_itself() || connection.peer_role() == Some(origin),
+			None => true,
+		}
+	}
+
+	fn is_authorized(&self, connection: &Connection) -> bool {
+		match &self.authorize {
+			Some(authorize) => authorize(connection),
+			None => true,
+		}
+	}
+
 	fn process(
 		&self,
+		handler_id: String,
 		connection: Arc<Connection>,
-		stream: stream::kind::Kind,
+		mut stream: stream::kind::Kind,
+		mut permit: stream::Permit,
 	) -> anyhow::Result<()> {
-		(self.fn_process)(connection, stream)
+		use stream::kind::Configure;
+		if let Some(timeout) = self.timeout {
+			let weak = Arc::downgrade(&connection);
+			// The streams abort themselves when the deadline expires (once they are configured with it).
+			let deadline = stream::Deadline::start(timeout, move |expired| {
+				use crate::connection::{event::Event, Active};
+				let connection = match weak.upgrade() {
+					Some(connection) => connection,
+					None => return,
+				};
+				log::warn!(
+					target: &connection.log_target(),
+					"Receiver for handler id {} timed out: {}",
+					handler_id,
+					expired
+				);
+				if let Ok(endpoint) = connection.endpoint() {
+					endpoint.send_connection_event(Event::TimedOut(
+						connection.remote_address(),
+						handler_id,
+					));
+				}
+			});
+			permit.set_deadline(deadline.clone());
+			let mut config = stream.config().clone();
+			config.deadline = Some(deadline);
+			stream.configure(&config);
+		}
+		(self.fn_process)(connection, stream, permit)
 	}
 }
 
+/// A change to the handlers of a [`Registry`], identified by their [`unique ids`](stream::Identifier::unique_id).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+	Registered(&'static str),
+	/// A handler was registered with the same id as a handler which was already registered, replacing it.
+	Replaced(&'static str),
+	Unregistered(&'static str),
+}
+
 /// Repository of stream identifiers.
 ///
 /// Used to read the [`unique_id`](stream::Identifier::unique_id) from an incoming stream
 /// and hand off the stream handling to a unique receiver of a given type.
+///
+/// Handlers can be registered and unregistered at any time, even while endpoints are using the registry
+/// (such as when a plugin is loaded). Streams which have already been handed to a receiver are unaffected.
 pub struct Registry {
 	/// The map of [`unique ids`](stream::Identifier::unique_id) to the registration for all registered builders.
-	registrations: HashMap<&'static str, Registered>,
+	/// Lookups only hold the lock long enough to clone the registration.
+	registrations: RwLock<HashMap<&'static str, Arc<Registered>>>,
+	changes: broadcast::Sender<Change>,
+	interceptors: RwLock<Vec<Arc<dyn stream::Interceptor>>>,
+	/// The limits applied to all of the handlers of each connection.
+	connection_limits: RwLock<Option<stream::Limits>>,
 }
 
+/// The limiter of a connection, for the [`connection limits`](Registry::limit_connections) of its registry.
+#[derive(Clone)]
+struct ConnectionLimiter(Arc<stream::Limiter>);
+
 impl Default for Registry {
 	fn default() -> Self {
 		Self {
-			registrations: HashMap::new(),
+			registrations: RwLock::new(HashMap::new()),
+			changes: broadcast::channel(Self::CHANGE_CAPACITY).0,
+			interceptors: RwLock::new(Vec::new()),
+			connection_limits: RwLock::new(None),
 		}
 	}
 }
 
 impl Registry {
+	/// The number of [`changes`](Self::subscribe) which are kept for subscribers that have not received them yet.
+	pub const CHANGE_CAPACITY: usize = 64;
+
 	/// Registers some [`identifier`](stream::Identifier) so that it can create a
 	/// [`receiver`](stream::handler::Receiver) when a packet with the provided id is received.
-	pub fn register<T>(&mut self, identifier: T)
+	/// Replaces any handler which was registered with the same id.
+	///
+	/// The handler accepts streams from any connection,
+	/// see [`register_with`](Self::register_with) to restrict which connections may use it.
+	pub fn register<T>(&self, identifier: T)
+	where
+		T: stream::Identifier + Send + Sync + 'static,
+		<T as stream::Identifier>::RecvBuilder: stream::recv::AppContext + Send + Sync + 'static,
+		<<T as stream::Identifier>::RecvBuilder as stream::recv::AppContext>::Receiver:
+			stream::handler::Receiver
+				+ From<stream::recv::Context<<T as stream::Identifier>::RecvBuilder>>,
+	{
+		self.register_with(identifier, |_| {});
+	}
+
+	/// Registers some [`identifier`](stream::Identifier) like [`register`](Self::register),
+	/// once the provided function has configured its [`Registration`]
+	/// (such as restricting the connections that may use the handler).
+	///
+	/// The handler is only added to the registry after the function returns,
+	/// so that it never accepts streams before its restrictions apply.
+	pub fn register_with<T, F>(&self, identifier: T, configure: F)
 	where
 		T: stream::Identifier + Send + Sync + 'static,
 		<T as stream::Identifier>::RecvBuilder: stream::recv::AppContext + Send + Sync + 'static,
 		<<T as stream::Identifier>::RecvBuilder as stream::recv::AppContext>::Receiver:
 			stream::handler::Receiver
 				+ From<stream::recv::Context<<T as stream::Identifier>::RecvBuilder>>,
+		F: FnOnce(&mut Registration),
 	{
-		self.registrations
-			.insert(T::unique_id(), Registered::from(identifier));
+		let id = T::unique_id();
+		let mut registration = Registration(Registered::from(identifier));
+		configure(&mut registration);
+		let replaced = self
+			.registrations
+			.write()
+			.unwrap()
+			.insert(id, Arc::new(registration.0))
+			.is_some();
+		let _ = self.changes.send(match replaced {
+			true => Change::Replaced(id),
+			false => Change::Registered(id),
+		});
+	}
+
+	/// Registers some [`identifier`](stream::Identifier) like [`register`](Self::register),
+	/// but only creates a [`receiver`](stream::handler::Receiver) for streams whose connection
+	/// is authorized by the provided predicate (such as only connections with a particular
+	/// [`fingerprint`](Connection::fingerprint) or [`extension`](Connection::extensions)).
+	///
+	/// The predicate is checked when each stream is received. Streams of unauthorized connections are
+	/// [`rejected`](stream::kind::Kind::reject) with [`PERMISSION_DENIED`](stream::kind::code::PERMISSION_DENIED),
+	/// counted in the handler's [`denials`](Self::denials), and reported to the endpoint as a
+	/// [`Denied`](crate::connection::event::Event::Denied) event.
+	pub fn register_authorized<T, F>(&self, identifier: T, authorize: F)
+	where
+		T: stream::Identifier + Send + Sync + 'static,
+		<T as stream::Identifier>::RecvBuilder: stream::recv::AppContext + Send + Sync + 'static,
+		<<T as stream::Identifier>::RecvBuilder as stream::recv::AppContext>::Receiver:
+			stream::handler::Receiver
+				+ From<stream::recv::Context<<T as stream::Identifier>::RecvBuilder>>,
+		F: Fn(&Connection) -> bool + Send + Sync + 'static,
+	{
+		self.register_with(identifier, |registration| {
+			registration.authorize(authorize);
+		});
+	}
+
+	/// Removes the handler of an [`identifier`](stream::Identifier), returning true if it was registered.
+	/// Streams for the handler which are received afterwards are rejected like any other unknown handler.
+	pub fn unregister<T>(&self) -> bool
+	where
+		T: stream::Identifier,
+	{
+		let id = T::unique_id();
+		let removed = self.registrations.write().unwrap().remove(id).is_some();
+		if removed {
+			let _ = self.changes.send(Change::Unregistered(id));
+		}
+		removed
+	}
+
+	/// Returns the ids of all registered handlers.
+	pub fn ids(&self) -> Vec<&'static str> {
+		self.registrations.read().unwrap().keys().copied().collect()
+	}
+
+	/// Subscribes to the [`changes`](Change) made to the registry after this call,
+	/// such as to tell peers about newly available handlers.
+	pub fn subscribe(&self) -> broadcast::Receiver<Change> {
+		self.changes.subscribe()
+	}
+
+	/// Returns the number of streams for a handler which were rejected because their connection was not authorized.
+	pub fn denials<T>(&self) -> u64
+	where
+		T: stream::Identifier,
+	{
+		self.lookup(T::unique_id())
+			.map(|registered| registered.denials.load(Ordering::Relaxed))
+			.unwrap_or(0)
 	}
 
 	/// Finds a builder based on the id of a given identifier.
@@ -79,10 +274,7 @@ impl Registry {
 		T: stream::Identifier + Send + Sync + 'static,
 	{
 		let id: &'static str = <T as stream::Identifier>::unique_id();
-		let reg = self
-			.registrations
-			.get(&id)
-			.ok_or(Error::NoSuchRegistration(id))?;
+		let reg = self.lookup(id).ok_or(Error::NoSuchRegistration(id))?;
 		let identifier = reg
 			.identifier
 			.clone()
@@ -90,34 +282,349 @@ impl Registry {
 			.map_err(|_| Error::RegistrationTypeMismatch(id))?;
 		Ok(identifier)
 	}
+
+	fn lookup(&self, id: &str) -> Option<Arc<Registered>> {
+		self.registrations.read().unwrap().get(id).cloned()
+	}
+
+	/// Adds an [`interceptor`](stream::Interceptor), which is called (after all interceptors added before it)
+	/// for every stream of every handler in the registry.
+	pub fn intercept<I>(&self, interceptor: I)
+	where
+		I: stream::Interceptor + 'static,
+	{
+		self.interceptors
+			.write()
+			.unwrap()
+			.push(Arc::new(interceptor));
+	}
+
+	/// Returns the interceptors of a connection in the order they are called (see [`Interceptor`](stream::Interceptor)).
+	fn interceptors(connection: &Connection) -> Vec<Arc<dyn stream::Interceptor>> {
+		// The interceptors are cloned so that an interceptor can add another without a deadlock.
+		let mut interceptors = Vec::new();
+		let base = connection.base_registry().ok();
+		if let Some(base) = &base {
+			interceptors.extend(base.interceptors.read().unwrap().iter().cloned());
+		}
+		if let Some(scope) = connection.scope() {
+			// `Option::is_some_and` would raise the minimum supported Rust version to 1.70.
+			#[allow(clippy::unnecessary_map_or)]
+			let same = base.map_or(false, |base| Arc::ptr_eq(&base, &scope));
+			if !same {
+				interceptors.extend(scope.interceptors.read().unwrap().iter().cloned());
+			}
+		}
+		interceptors
+	}
+
+	/// Limits the streams received by each connection, across all of the handlers in the registry
+	/// (see [`Limits`](stream::Limits)). Handlers can have their own limits as well, see [`limit`](Registration::limit).
+	///
+	/// Each connection keeps the limits which applied when it received its first stream.
+	pub fn limit_connections(&self, limits: stream::Limits) {
+		*self.connection_limits.write().unwrap() = Some(limits);
+	}
+
+	/// Checks an incoming stream against the limits of its connection, before anything is read from the stream
+	/// (see [`Limiter::check`](stream::Limiter::check)). Streams are always admitted if there are no limits.
+	fn check_connection(
+		&self,
+		connection: &Arc<Connection>,
+	) -> Result<Option<(Arc<stream::Limiter>, stream::Admission)>, stream::Overflow> {
+		let connection_limits = *self.connection_limits.read().unwrap();
+		let limiter = match connection_limits {
+			Some(limits) => {
+				connection
+					.extensions()
+					.get_or_insert_with(|| ConnectionLimiter(Arc::new(limits.into())))
+					.0
+			}
+			None => return Ok(None),
+		};
+		let admission = limiter.check();
+		if !matches!(admission, Ok(stream::Admission::Admitted(_))) {
+			// The handler id has not been read yet.
+			Self::limited(connection, "");
+		}
+		Ok(Some((limiter, admission?)))
+	}
+
+	/// Admits an incoming stream within the limits of its handler,
+	/// or returns the [`overflow`](stream::Overflow) policy to apply to it.
+	async fn admit(
+		registered: &Registered,
+		connection: &Arc<Connection>,
+		handler_id: &str,
+	) -> Result<stream::Permit, stream::Overflow> {
+		let limiter = match &registered.limiter {
+			Some(limiter) => limiter,
+			None => return Ok(stream::Permit::default()),
+		};
+		let admission = limiter.check();
+		if !matches!(admission, Ok(stream::Admission::Admitted(_))) {
+			Self::limited(connection, handler_id);
+		}
+		match admission? {
+			stream::Admission::Admitted(permit) => Ok(permit),
+			stream::Admission::Queued(_queued) => Ok(limiter.admit().await),
+		}
+	}
+
+	/// Reports a stream which exceeded some limits to the endpoint.
+	fn limited(connection: &Connection, handler_id: &str) {
+		if let Ok(endpoint) = connection.endpoint() {
+			use crate::connection::{event::Event, Active};
+			endpoint.send_connection_event(Event::Limited(
+				connection.remote_address(),
+				handler_id.to_owned(),
+			));
+		}
+	}
+
+	/// Calls the interceptors for a stream which is about to be opened,
+	/// returning the interceptors to [`complete`](stream::Completion::complete) once the stream is opened.
+	pub(crate) fn intercept_open(
+		handler_id: &'static str,
+		connection: &Arc<Connection>,
+		kind: stream::capture::Kind,
+	) -> anyhow::Result<stream::Completion> {
+		let stream = stream::Interception {
+			handler_id,
+			connection,
+			kind,
+			rejection: None,
+		};
+		let (flow, completion) = stream::Completion::run(
+			Self::interceptors(connection),
+			&stream,
+			|interceptor, stream| interceptor.open(stream),
+		);
+		match flow {
+			stream::Flow::Continue => Ok(completion),
+			stream::Flow::Reject(code) => {
+				let error = Error::Intercepted(handler_id, code).into();
+				completion.complete(&stream::Outcome::Failed(&error));
+				Err(error)
+			}
+		}
+	}
+}
+
+/// The registration of a handler in a [`Registry`], configured by [`register_with`](Registry::register_with).
+pub struct Registration(Registered);
+
+impl Registration {
+	fn registered(&mut self) -> &mut Registered {
+		&mut self.0
+	}
+
+	/// Only accepts streams for the handler which were opened by peers with the provided role
+	/// (such as a handler for updates which only the server pushes to clients).
+	///
+	/// Streams opened by other peers are [`rejected`](stream::kind::Kind::reject)
+	/// with [`WRONG_DIRECTION`](stream::kind::code::WRONG_DIRECTION).
+	/// The role of a peer is determined by the [`role`](crate::endpoint::Endpoint::role) of the receiving endpoint
+	/// (see [`peer_role`](Connection::peer_role)).
+	/// Streams an endpoint opens on a connection to itself are always accepted, whatever the role.
+	pub fn accept_from(&mut self, role: Role) -> &mut Self {
+		self.registered().origin = Some(role);
+		self
+	}
+
+	/// Limits the streams received for the handler, across all connections (see [`Limits`](stream::Limits)).
+	/// Streams are only limited once the connection is allowed to use the handler, and after any
+	/// [`connection limits`](Registry::limit_connections) have admitted them.
+	pub fn limit(&mut self, limits: stream::Limits) -> &mut Self {
+		self.registered().limiter = Some(limits.into());
+		self
+	}
+
+	/// Bounds how long each receiver of the handler may take (see [`Timeout`](stream::Timeout)).
+	///
+	/// Once a receiver times out, its pending and later reads and writes fail with [`TimedOut`](stream::TimedOut).
+	/// The streams are stopped and reset with [`TIMED_OUT`](stream::kind::code::TIMED_OUT),
+	/// so the opener's reads and writes fail with [`TimedOut::Peer`](stream::TimedOut::Peer).
+	/// The timeout is reported to the endpoint as a [`TimedOut`](crate::connection::event::Event::TimedOut) event,
+	/// even if the receiver is not using its streams.
+	pub fn timeout(&mut self, timeout: stream::Timeout) -> &mut Self {
+		self.registered().timeout = Some(timeout);
+		self
+	}
+
+	/// Only accepts streams for the handler whose connection is authorized by the provided predicate.
+	///
+	/// See [`register_authorized`](Registry::register_authorized).
+	pub fn authorize<F>(&mut self, authorize: F) -> &mut Self
+	where
+		F: Fn(&Connection) -> bool + Send + Sync + 'static,
+	{
+		self.registered().authorize = Some(Box::new(authorize));
+		self
+	}
 }
 
 impl Registry {
 	/// Creates the receiver and spawns the process for an incoming stream of any kind.
 	///
+	/// The handler is found in the current [`registry`](Connection::registry) of the connection
+	/// (once the handler id has been read), so streams for handlers which are not in the connection's
+	/// current scope are [`rejected`](stream::kind::Kind::reject) with [`UNKNOWN_HANDLER`](stream::kind::code::UNKNOWN_HANDLER).
+	///
 	/// This function spawns its own async task/future, so all passed params
 	/// will start to be processed but the call itself is non-blocking.
-	pub(crate) fn create_receiver(
-		self: Arc<Self>,
-		connection: Arc<Connection>,
-		mut stream: stream::kind::Kind,
-	) {
+	pub(crate) fn create_receiver(connection: Arc<Connection>, mut stream: stream::kind::Kind) {
 		let log = connection.log_target();
+		// The limits of the connection are checked before any task is spawned for the stream,
+		// so a peer can not open more streams than the connection can queue.
+		let checked = match connection.registry() {
+			Ok(registry) => registry.check_connection(&connection),
+			Err(_) => Ok(None),
+		};
+		let admission = match checked {
+			Ok(checked) => checked,
+			Err(overflow) => {
+				log::debug!(
+					target: &log,
+					"Stream exceeded the limits of its connection, applying {:?}",
+					overflow
+				);
+				if overflow == stream::Overflow::Reset {
+					use futures_util::FutureExt;
+					// Rejecting a stream never waits.
+					let _ = stream
+						.reject(stream::kind::code::LIMIT_EXCEEDED)
+						.now_or_never();
+				}
+				return;
+			}
+		};
 		crate::utility::spawn(log.clone(), async move {
+			use crate::connection::Active;
+			use stream::{capture, kind::Configure};
+			let mut permit = match admission {
+				Some((limiter, stream::Admission::Queued(_queued))) => limiter.admit().await,
+				Some((_, stream::Admission::Admitted(permit))) => permit,
+				None => stream::Permit::default(),
+			};
+			// The endpoint's configuration applies until the handler is known (and can adjust the configuration).
+			let mut config = connection.stream_config()?;
+			let recorder = connection
+				.endpoint()
+				.ok()
+				.and_then(|endpoint| endpoint.recorder());
+			if let Some(recorder) = recorder {
+				let kind = capture::Kind::from(&stream);
+				config.capture =
+					Some(recorder.tap(kind, capture::Initiator::Peer, connection.remote_address()));
+			}
+			stream.configure(&config);
+			if let stream::kind::Kind::Bidirectional((send, _)) = &mut stream {
+				connection.accept_send(send);
+			}
 			let handler_id = stream
 				.read_handler_id()
 				.await
 				.context("reading handler id")?;
-			match self.registrations.get(handler_id.as_str()) {
-				Some(registered) => {
-					registered.process(connection, stream)?;
+			if let Some(tap) = &stream.config().capture {
+				tap.record(capture::Event::Identified(handler_id.clone()));
+			}
+			// The scope of the connection may have changed while the id was being read.
+			let registry = connection.registry()?;
+			let registered = registry.lookup(handler_id.as_str());
+			let rejection = match &registered {
+				None => Some(stream::kind::code::UNKNOWN_HANDLER),
+				Some(registered) if !registered.accepts_from(&connection) => {
+					Some(stream::kind::code::WRONG_DIRECTION)
+				}
+				Some(registered) if !registered.is_authorized(&connection) => {
+					Some(stream::kind::code::PERMISSION_DENIED)
+				}
+				Some(_) => None,
+			};
+			// Interceptors see every stream, even those which are rejected anyway.
+			let (flow, completion) = stream::Completion::run(
+				Self::interceptors(&connection),
+				&stream::Interception {
+					handler_id: &handler_id,
+					connection: &connection,
+					kind: capture::Kind::from(&stream),
+					rejection,
+				},
+				|interceptor, stream| interceptor.receive(stream),
+			);
+			let registered = match (registered, rejection) {
+				(Some(registered), None) => registered,
+				(registered, rejection) => {
+					let code = rejection.unwrap_or(stream::kind::code::UNKNOWN_HANDLER);
+					match registered {
+						None => log::error!(
+							target: &log,
+							"Rejected stream for handler id {}, which is not in the registry of the connection",
+							handler_id
+						),
+						Some(_) if code == stream::kind::code::WRONG_DIRECTION => log::warn!(
+							target: &log,
+							"Rejected stream for handler id {}, which does not accept streams from a {:?}",
+							handler_id,
+							connection.peer_role()
+						),
+						Some(registered) => {
+							registered.denials.fetch_add(1, Ordering::Relaxed);
+							log::warn!(
+								target: &log,
+								"Rejected stream for handler id {}, the connection is not authorized to use it",
+								handler_id
+							);
+							if let Ok(endpoint) = connection.endpoint() {
+								use crate::connection::event::Event;
+								endpoint.send_connection_event(Event::Denied(
+									connection.remote_address(),
+									handler_id,
+								));
+							}
+						}
+					}
+					completion.complete(&stream::Outcome::Rejected(code));
+					stream.reject(code).await?;
+					return Ok(());
+				}
+			};
+			match flow {
+				stream::Flow::Continue => {
+					match Self::admit(&registered, &connection, &handler_id).await {
+						Ok(admitted) => {
+							permit.join(admitted);
+							permit.complete_with(completion);
+							registered.process(handler_id, connection, stream, permit)?
+						}
+						Err(overflow) => {
+							log::debug!(
+								target: &log,
+								"Stream for handler id {} exceeded its limits, applying {:?}",
+								handler_id,
+								overflow
+							);
+							match overflow {
+								stream::Overflow::Reset => {
+									let code = stream::kind::code::LIMIT_EXCEEDED;
+									completion.complete(&stream::Outcome::Rejected(code));
+									stream.reject(code).await?;
+								}
+								_ => completion.complete(&stream::Outcome::Dropped),
+							}
+						}
+					}
 				}
-				None => {
-					log::error!(
+				stream::Flow::Reject(code) => {
+					log::debug!(
 						target: &log,
-						"Failed to find stream handler for id {}",
-						handler_id
+						"Rejected stream for handler id {} with code {}, by an interceptor",
+						handler_id,
+						code
 					);
+					completion.complete(&stream::Outcome::Rejected(code));
+					stream.reject(code).await?;
 				}
 			}
 			Ok(())
@@ -131,4 +638,6 @@ pub enum Error {
 	NoSuchRegistration(&'static str),
 	#[error("Tried to get registered identifier for id({0}), but the registration could not be downcast to the provided type.")]
 	RegistrationTypeMismatch(&'static str),
+	#[error("Opening a stream for id({0}) was rejected by an interceptor with code {1}.")]
+	Intercepted(&'static str, u32),
 }
@@ -15,6 +15,12 @@ pub trait AppContext {
 	/// The kind of stream that this builder should create.
 	type Opener: stream::Opener;
 
+	/// Adjusts the endpoint's [`stream configuration`](stream::Config) for streams opened by this builder.
+	/// The configuration is applied after the handler id has been written to the stream.
+	///
+	/// Does nothing by default, so streams use the configuration of the endpoint.
+	fn configure(&self, _config: &mut stream::Config) {}
+
 	fn open(
 		connection: Arc<Connection>,
 	) -> PinFutureResult<<Self::Opener as stream::Opener>::Output> {
@@ -0,0 +1,197 @@
+use std::{
+	future::Future,
+	sync::{Arc, Mutex, Weak},
+	time::Duration,
+};
+use tokio::time::Instant;
+
+/// How long the receiver of a handler may take, see [`Registration::timeout`](crate::stream::Registration::timeout).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timeout {
+	/// The longest a receiver may use its streams, from when the stream was handed to it.
+	pub total: Option<Duration>,
+	/// The longest a receiver may wait between reads or writes of its streams
+	/// (such as while waiting for the peer to send the next message).
+	pub idle: Option<Duration>,
+}
+
+/// The error returned by stream operations once the handler of the stream has timed out.
+#[derive(thiserror::Error, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimedOut {
+	#[error("The handler of the stream exceeded its total timeout.")]
+	Total,
+	#[error("The handler of the stream was idle for longer than its timeout.")]
+	Idle,
+	/// The peer's handler of the stream timed out, and reset the stream with [`TIMED_OUT`](crate::stream::kind::code::TIMED_OUT).
+	#[error("The peer's handler of the stream timed out.")]
+	Peer,
+	/// A read or write did not complete before the deadline it was given
+	/// (see [`read_deadline`](crate::stream::kind::Read::read_deadline)
+	/// and [`write_deadline`](crate::stream::kind::Write::write_deadline)).
+	#[error("The stream operation did not complete before its deadline.")]
+	Deadline,
+}
+
+struct State {
+	timeout: Timeout,
+	started: Instant,
+	last_active: Instant,
+	expired: Option<TimedOut>,
+	/// Stop or reset the streams using the deadline, once it expires.
+	aborts: Vec<Box<dyn FnOnce() + Send>>,
+}
+
+impl State {
+	/// Returns the next time the deadline could expire (if ever), and how it would expire.
+	fn next(&self) -> Option<(Instant, TimedOut)> {
+		let total = self
+			.timeout
+			.total
+			.map(|total| (self.started + total, TimedOut::Total));
+		let idle = self
+			.timeout
+			.idle
+			.map(|idle| (self.last_active + idle, TimedOut::Idle));
+		match (total, idle) {
+			(Some(total), Some(idle)) => Some(if idle.0 < total.0 { idle } else { total }),
+			(total, idle) => total.or(idle),
+		}
+	}
+
+	fn check(&mut self) -> Result<(), TimedOut> {
+		if let Some(expired) = self.expired {
+			return Err(expired);
+		}
+		match self.next() {
+			Some((at, expired)) if at <= Instant::now() => {
+				self.expired = Some(expired);
+				Err(expired)
+			}
+			_ => Ok(()),
+		}
+	}
+}
+
+/// Enforces the [`Timeout`] of a receiver on all of its streams.
+///
+/// Provided to a stream via its [`config`](crate::stream::Config::deadline)
+/// when the stream's handler has a timeout, and shared by every clone of the config.
+#[derive(Clone)]
+pub struct Deadline(Arc<Mutex<State>>);
+
+impl std::fmt::Debug for Deadline {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "Deadline({:?})", self.0.lock().unwrap().timeout)
+	}
+}
+
+impl Deadline {
+	/// Starts the deadline, calling `on_expired` when it expires
+	/// (even if the receiver is not using its streams at the time).
+	/// Nothing is called if every clone of the deadline is dropped before it expires.
+	pub(crate) fn start<F>(timeout: Timeout, on_expired: F) -> Self
+	where
+		F: FnOnce(TimedOut) + Send + 'static,
+	{
+		let now = Instant::now();
+		let deadline = Self(Arc::new(Mutex::new(State {
+			timeout,
+			started: now,
+			last_active: now,
+			expired: None,
+			aborts: Vec::new(),
+		})));
+		let state = Arc::downgrade(&deadline.0);
+		tokio::task::spawn(async move {
+			if let Some((expired, aborts)) = Self::watch(state).await {
+				for abort in aborts {
+					abort();
+				}
+				on_expired(expired);
+			}
+		});
+		deadline
+	}
+
+	/// Waits until the deadline expires, returning how it expired and the streams to abort.
+	#[allow(clippy::type_complexity)]
+	async fn watch(state: Weak<Mutex<State>>) -> Option<(TimedOut, Vec<Box<dyn FnOnce() + Send>>)> {
+		loop {
+			let next = {
+				let state = state.upgrade()?;
+				let mut state = state.lock().unwrap();
+				if let Err(expired) = state.check() {
+					return Some((expired, std::mem::take(&mut state.aborts)));
+				}
+				state.next()?.0
+			};
+			tokio::time::sleep_until(next).await;
+		}
+	}
+
+	/// Returns the deadline a stream is being configured with, if the stream was not already using it
+	/// (so the stream only needs to be [`aborted`](Self::on_expired) once).
+	pub(crate) fn added<'a>(
+		previous: &Option<Deadline>,
+		next: &'a Option<Deadline>,
+	) -> Option<&'a Deadline> {
+		match (previous, next) {
+			(Some(previous), Some(next)) if Arc::ptr_eq(&previous.0, &next.0) => None,
+			(_, next) => next.as_ref(),
+		}
+	}
+
+	/// Calls `abort` once the deadline expires (or right away, if it already has),
+	/// so that a stream is stopped or reset even while its receiver is not using it.
+	/// Nothing is called if every clone of the deadline is dropped before it expires.
+	pub(crate) fn on_expired<F>(&self, abort: F)
+	where
+		F: FnOnce() + Send + 'static,
+	{
+		let mut state = self.0.lock().unwrap();
+		match state.expired {
+			Some(_) => {
+				drop(state);
+				abort();
+			}
+			None => state.aborts.push(Box::new(abort)),
+		}
+	}
+
+	/// Returns how the deadline expired, if it has.
+	pub(crate) fn expired(&self) -> Option<TimedOut> {
+		self.0.lock().unwrap().check().err()
+	}
+
+	/// Waits until the deadline expires.
+	async fn expiry(&self) -> TimedOut {
+		loop {
+			let next = {
+				let mut state = self.0.lock().unwrap();
+				if let Err(expired) = state.check() {
+					return expired;
+				}
+				state.next().map(|(next, _)| next)
+			};
+			match next {
+				Some(next) => tokio::time::sleep_until(next).await,
+				None => futures::future::pending::<()>().await,
+			}
+		}
+	}
+
+	/// Runs a stream operation, unless the deadline expires first.
+	/// The receiver is active again once the operation completes.
+	pub(crate) async fn bound<F, T>(&self, operation: F) -> Result<T, TimedOut>
+	where
+		F: Future<Output = T>,
+	{
+		self.0.lock().unwrap().check()?;
+		let output = tokio::select! {
+			output = operation => output,
+			expired = self.expiry() => return Err(expired),
+		};
+		self.0.lock().unwrap().last_active = Instant::now();
+		Ok(output)
+	}
+}
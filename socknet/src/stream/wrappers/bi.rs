@@ -17,6 +17,10 @@ impl stream::Opener for Opener {
 	fn open(connection: Arc<Connection>) -> PinFutureResult<Self::Output> {
 		Box::pin(async move { connection.open_bi().await })
 	}
+
+	fn kind() -> stream::capture::Kind {
+		stream::capture::Kind::Bidirectional
+	}
 }
 
 /// Parses the incoming [`send`](stream::kind::Send) & [`recv`](stream::kind::Recv) streams,
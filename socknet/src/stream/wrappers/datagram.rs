@@ -17,12 +17,17 @@ impl stream::Opener for Opener {
 	fn open(connection: Arc<Connection>) -> PinFutureResult<Self::Output> {
 		Box::pin(async move {
 			use stream::kind::send::datagram::{Datagram, Local, Remote};
+			let config = stream::Config::default();
 			Ok(match connection.is_local() {
-				true => Datagram::Local(Local(Vec::new(), connection)),
-				false => Datagram::Remote(Remote(Vec::new(), connection)),
+				true => Datagram::Local(Local(Vec::new(), connection, config)),
+				false => Datagram::Remote(Remote(Vec::new(), connection, config)),
 			})
 		})
 	}
+
+	fn kind() -> stream::capture::Kind {
+		stream::capture::Kind::Datagram
+	}
 }
 
 /// Parses the incoming [`buffer`](stream::kind::recv::Datagram),
@@ -17,6 +17,10 @@ impl stream::Opener for Opener {
 	fn open(connection: Arc<Connection>) -> PinFutureResult<Self::Output> {
 		Box::pin(async move { connection.open_uni().await })
 	}
+
+	fn kind() -> stream::capture::Kind {
+		stream::capture::Kind::Unidirectional
+	}
 }
 
 /// Parses the incoming [`recv stream`](stream::kind::Recv)
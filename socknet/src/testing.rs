@@ -0,0 +1,9 @@
+//! Support for testing stream handlers, enabled by the `testing` feature.
+
+#[doc(hidden)]
+mod mock;
+pub use mock::*;
+
+#[doc(hidden)]
+mod network;
+pub use network::*;
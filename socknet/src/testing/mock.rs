@@ -0,0 +1,171 @@
+use crate::{
+	connection::{
+		active,
+		opened::{self, Listeners},
+		CloseCode, Connection, LocalConfig,
+	},
+	stream::{
+		self,
+		kind::{recv, send, Configure, Kind},
+		local::CloseState,
+	},
+};
+use std::{net::SocketAddr, sync::Arc};
+
+/// A connection for testing a single stream handler in isolation, without any endpoints.
+///
+/// The mock is made of two in-memory [`connections`](Connection):
+/// the [`connection`](Self::connection) given to the handler under test,
+/// and the [`peer`](Self::peer) which the test uses to play the other side.
+/// Streams the peer opens are handed to the test by [`incoming`](Self::incoming)
+/// (to be [`received`](Self::receive) by a handler),
+/// and streams the handler opens are handed to the test by [`opened`](Self::opened),
+/// so everything the handler wrote to a stream can be read back.
+///
+/// Both connections use the provided registry and [`stream configuration`](stream::Config)
+/// in place of those of an endpoint.
+pub struct MockConnection {
+	connection: Arc<Connection>,
+	peer: Arc<Connection>,
+	stream_config: stream::Config,
+	closed: Arc<tokio::sync::watch::Sender<CloseState>>,
+	/// Streams opened by the peer, which have not yet been handed to the test.
+	incoming: Listeners,
+	/// Streams opened by the handler, which have not yet been handed to the test.
+	opened: Listeners,
+}
+
+impl Default for MockConnection {
+	fn default() -> Self {
+		Self::new(
+			Arc::new(stream::Registry::default()),
+			stream::Config::default(),
+		)
+	}
+}
+
+impl MockConnection {
+	/// The address reported for both sides of the mock connection.
+	pub const ADDRESS: SocketAddr = SocketAddr::V4(std::net::SocketAddrV4::new(
+		std::net::Ipv4Addr::LOCALHOST,
+		0,
+	));
+
+	pub fn new(registry: Arc<stream::Registry>, stream_config: stream::Config) -> Self {
+		Self::with_local_config(registry, stream_config, &LocalConfig::default())
+	}
+
+	/// Creates a mock whose streams and datagrams are queued according to the provided [`LocalConfig`].
+	pub fn with_local_config(
+		registry: Arc<stream::Registry>,
+		stream_config: stream::Config,
+		local_config: &LocalConfig,
+	) -> Self {
+		let (to_peer, opened) = opened::link(local_config);
+		let (to_handler, incoming) = opened::link(local_config);
+		let closed = Arc::new(tokio::sync::watch::channel(None).0);
+		let side = |outgoing: &active::Link, incoming: &active::Link| {
+			let active = active::Local {
+				peer_address: Self::ADDRESS,
+				peer_certificate: rustls::Certificate(Vec::new()),
+				outgoing: outgoing.clone(),
+				incoming: incoming.clone(),
+				stream_capacity: local_config.stream_capacity.max(1),
+				closed: closed.clone(),
+			};
+			Arc::new(Connection::standalone(
+				Box::new(active),
+				registry.clone(),
+				stream_config.clone(),
+			))
+		};
+		Self {
+			connection: side(&to_peer, &to_handler),
+			peer: side(&to_handler, &to_peer),
+			stream_config,
+			closed,
+			incoming,
+			opened,
+		}
+	}
+
+	/// The connection to provide to the handler under test.
+	pub fn connection(&self) -> &Arc<Connection> {
+		&self.connection
+	}
+
+	/// The other side of the connection, which is controlled by the test.
+	pub fn peer(&self) -> &Arc<Connection> {
+		&self.peer
+	}
+
+	/// Opens a unidirectional stream from the peer, which is received with [`incoming`](Self::incoming).
+	pub async fn open_uni(&self) -> anyhow::Result<send::Ongoing> {
+		use crate::connection::Active;
+		let mut stream = self.peer.open_uni().await?;
+		stream.configure(&self.stream_config);
+		Ok(stream)
+	}
+
+	/// Opens a bidirectional stream from the peer, which is received with [`incoming`](Self::incoming).
+	pub async fn open_bi(&self) -> anyhow::Result<(send::Ongoing, recv::Ongoing)> {
+		use crate::connection::Active;
+		let mut streams = self.peer.open_bi().await?;
+		streams.configure(&self.stream_config);
+		Ok(streams)
+	}
+
+	/// Creates a datagram from the peer, which is received with [`incoming`](Self::incoming)
+	/// once it is [`finished`](crate::stream::kind::Send::finish).
+	pub fn open_datagram(&self) -> send::Datagram {
+		send::Datagram::Local(send::datagram::Local(
+			Vec::new(),
+			self.peer.clone(),
+			self.stream_config.clone(),
+		))
+	}
+
+	/// Waits for the next stream (or datagram) opened by the peer.
+	pub async fn incoming(&self) -> anyhow::Result<Kind> {
+		self.next(&self.incoming).await
+	}
+
+	/// Waits for the next stream (or datagram) opened by the handler,
+	/// as it would be received by the peer.
+	///
+	/// Streams opened through an [`Identifier`](stream::Identifier) start with the id of their handler,
+	/// which can be read with [`read_handler_id`](Kind::read_handler_id).
+	pub async fn opened(&self) -> anyhow::Result<Kind> {
+		self.next(&self.opened).await
+	}
+
+	/// Hands a stream to the receiver of a builder, like the registry would for
+	/// a stream whose handler id matched the builder (the handler id is not read from the stream).
+	pub fn receive<T>(&self, builder: Arc<T>, mut stream: Kind) -> anyhow::Result<()>
+	where
+		T: stream::recv::AppContext,
+		T::Receiver: From<stream::recv::Context<T>>,
+	{
+		let mut config = self.stream_config.clone();
+		builder.configure(&mut config);
+		stream.configure(&config);
+		let context = builder.into_context(self.connection.clone(), stream)?;
+		T::process(context);
+		Ok(())
+	}
+
+	/// Returns the code and reason the connection was closed with (by either side), if it has been closed.
+	pub fn close_reason(&self) -> Option<(CloseCode, String)> {
+		self.closed.borrow().clone()
+	}
+
+	async fn next(&self, listeners: &Listeners) -> anyhow::Result<Kind> {
+		let mut stream: Kind = tokio::select! {
+			item = listeners.uni_streams.recv() => item?.map(Kind::from)?,
+			item = listeners.bi_streams.recv() => item?.map(Kind::from)?,
+			item = listeners.datagrams.recv() => item?.map(Kind::from)?,
+		};
+		stream.configure(&self.stream_config);
+		Ok(stream)
+	}
+}
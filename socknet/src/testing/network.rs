@@ -0,0 +1,201 @@
+use crate::{
+	connection::{event::Event, Connection, LocalConfig},
+	endpoint::{self, Endpoint},
+	stream,
+};
+use std::{
+	net::SocketAddr,
+	sync::{Arc, Weak},
+	time::Duration,
+};
+
+/// How long the helpers of this module wait for something to happen before failing.
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The name which identities are generated for, and which clients connect to the server with.
+pub const SERVER_NAME: &str = "localhost";
+
+/// Generates an ephemeral self-signed certificate (for [`SERVER_NAME`]) and its private key.
+pub fn identity() -> anyhow::Result<(rustls::Certificate, rustls::PrivateKey)> {
+	let generated = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_owned()])?;
+	Ok((
+		rustls::Certificate(generated.serialize_der()?),
+		rustls::PrivateKey(generated.serialize_private_key_der()),
+	))
+}
+
+/// An endpoint of a [`Network`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Peer {
+	Server,
+	/// The client at some index of [`clients`](Network::clients).
+	Client(usize),
+}
+
+/// A server and any number of clients, bound to ephemeral ports on the loopback interface.
+///
+/// Every endpoint has its own generated identity, and clients trust the identity of the server.
+pub struct Network {
+	pub server: Arc<Endpoint>,
+	pub clients: Vec<Arc<Endpoint>>,
+}
+
+impl Network {
+	/// Creates a server and `clients` clients, which all use the same stream configuration.
+	/// The registry of each endpoint is created by calling `registry` with the endpoint it is for.
+	///
+	/// Must be called from within a tokio runtime.
+	pub fn new<F>(
+		clients: usize,
+		stream_config: stream::Config,
+		registry: F,
+	) -> anyhow::Result<Self>
+	where
+		F: FnMut(Peer) -> stream::Registry,
+	{
+		Self::with_local_config(clients, stream_config, LocalConfig::default(), registry)
+	}
+
+	/// Creates a network like [`new`](Self::new), whose endpoints all use the same [`local configuration`](LocalConfig).
+	pub fn with_local_config<F>(
+		clients: usize,
+		stream_config: stream::Config,
+		local_config: LocalConfig,
+		mut registry: F,
+	) -> anyhow::Result<Self>
+	where
+		F: FnMut(Peer) -> stream::Registry,
+	{
+		let address: SocketAddr = "127.0.0.1:0".parse()?;
+
+		let (certificate, private_key) = identity()?;
+		let mut roots = rustls::RootCertStore::empty();
+		roots.add(&certificate)?;
+		let core =
+			quinn::ServerConfig::with_single_cert(vec![certificate.clone()], private_key.clone())?;
+		let server = crate::Config {
+			endpoint: endpoint::Config::Server(endpoint::ServerConfig {
+				core,
+				certificate,
+				private_key,
+			}),
+			address,
+			stream_registry: Arc::new(registry(Peer::Server)),
+			stream_config: stream_config.clone(),
+			local_config: local_config.clone(),
+		}
+		.build()?;
+
+		let clients = (0..clients)
+			.map(|index| {
+				let (certificate, private_key) = identity()?;
+				crate::Config {
+					endpoint: endpoint::Config::Client(endpoint::ClientConfig {
+						core: quinn::ClientConfig::with_root_certificates(roots.clone()),
+						certificate,
+						private_key,
+					}),
+					address,
+					stream_registry: Arc::new(registry(Peer::Client(index))),
+					stream_config: stream_config.clone(),
+					local_config: local_config.clone(),
+				}
+				.build()
+			})
+			.collect::<anyhow::Result<Vec<_>>>()?;
+
+		Ok(Self { server, clients })
+	}
+
+	pub fn endpoint(&self, peer: Peer) -> &Arc<Endpoint> {
+		match peer {
+			Peer::Server => &self.server,
+			Peer::Client(index) => &self.clients[index],
+		}
+	}
+
+	/// Connects some endpoint to the server.
+	/// The connection is [`remote`](crate::stream::kind::Locality::Remote) for clients,
+	/// and [`local`](crate::stream::kind::Locality::Local) for the server itself.
+	pub async fn connect(&self, peer: Peer) -> anyhow::Result<Weak<Connection>> {
+		let connecting = self
+			.endpoint(peer)
+			.connect(self.server.address(), SERVER_NAME.to_owned());
+		tokio::time::timeout(TIMEOUT, connecting).await?
+	}
+}
+
+/// Waits for the next connection to be [`created`](Event::Created) by an endpoint,
+/// skipping any other events.
+pub async fn wait_for_created(endpoint: &Endpoint) -> anyhow::Result<Weak<Connection>> {
+	tokio::time::timeout(TIMEOUT, async {
+		loop {
+			if let Event::Created(connection) = endpoint.connection_receiver().recv().await? {
+				return Ok(connection);
+			}
+		}
+	})
+	.await?
+}
+
+/// Waits for the next connection of an endpoint to be [`dropped`](Event::Dropped),
+/// skipping any other events.
+pub async fn wait_for_dropped(endpoint: &Endpoint) -> anyhow::Result<SocketAddr> {
+	tokio::time::timeout(TIMEOUT, async {
+		loop {
+			if let Event::Dropped(address) = endpoint.connection_receiver().recv().await? {
+				return Ok(address);
+			}
+		}
+	})
+	.await?
+}
+
+/// Creates a channel for handlers to report the messages they receive,
+/// and the [`Messages`] used by a test to make assertions on them.
+pub fn messages<T>() -> (async_channel::Sender<T>, Messages<T>) {
+	let (sender, receiver) = async_channel::unbounded();
+	(sender, Messages(receiver))
+}
+
+/// The messages reported by handlers, in the order they were received.
+pub struct Messages<T>(async_channel::Receiver<T>);
+
+impl<T> Messages<T> {
+	/// Waits for the next message.
+	pub async fn next(&self) -> anyhow::Result<T> {
+		Ok(tokio::time::timeout(TIMEOUT, self.0.recv()).await??)
+	}
+
+	/// Waits for the next `count` messages.
+	pub async fn take(&self, count: usize) -> anyhow::Result<Vec<T>> {
+		let mut messages = Vec::with_capacity(count);
+		for _ in 0..count {
+			messages.push(self.next().await?);
+		}
+		Ok(messages)
+	}
+
+	/// Panics if there are any messages which have not been taken.
+	pub fn assert_empty(&self)
+	where
+		T: std::fmt::Debug,
+	{
+		if let Ok(message) = self.0.try_recv() {
+			panic!("expected no more messages, but received {:?}", message);
+		}
+	}
+}
+
+impl<T> Messages<T>
+where
+	T: std::fmt::Debug + PartialEq,
+{
+	/// Waits for the next message, and panics if it is not the expected message.
+	pub async fn assert_next(&self, expected: &T) {
+		match self.next().await {
+			Ok(message) => assert_eq!(&message, expected),
+			Err(error) => panic!("expected {:?}, but received nothing: {:?}", expected, error),
+		}
+	}
+}
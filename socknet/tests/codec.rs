@@ -0,0 +1,174 @@
+//! Every codec, over every kind of stream and connection.
+#![cfg(feature = "testing")]
+
+mod common;
+
+use common::{bi, datagram, uni, AppContext};
+use serde::{Deserialize, Serialize};
+use socknet::{
+	connection::Connection,
+	stream::{self, codec::Format},
+	testing::{self, Messages, Network, Peer},
+};
+use std::sync::{Arc, Weak};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+enum Shape {
+	Point,
+	Circle(f32),
+	Rect { width: u16, height: u16 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Payload {
+	name: String,
+	values: Vec<u32>,
+	offset: Option<i64>,
+	shape: Shape,
+}
+
+impl Payload {
+	fn sample() -> Self {
+		Self {
+			name: "chunk[3, -2]".to_owned(),
+			values: vec![0, 1, 255, 65536, u32::MAX],
+			offset: Some(-42),
+			shape: Shape::Rect {
+				width: 16,
+				height: 9,
+			},
+		}
+	}
+}
+
+/// Creates a network whose handlers all use the codec, and whose server reports every payload it receives.
+fn network(codec: Format, stream_config: stream::Config) -> (Network, Messages<Payload>) {
+	let (received, messages) = testing::messages();
+	let network = Network::new(1, stream_config, |_peer| {
+		let mut context = AppContext::new(received.clone());
+		context.codec = Some(codec);
+		common::registry(Arc::new(context))
+	})
+	.unwrap();
+	(network, messages)
+}
+
+/// Creates the network for one case of the matrix, and connects to its server.
+/// Local connections only run the codec when their values are [`serialized`](stream::Config::serialize_local),
+/// so local cases are always serialized.
+async fn connect(codec: Format, local: bool) -> (Network, Messages<Payload>, Weak<Connection>) {
+	let stream_config = stream::Config {
+		serialize_local: local,
+		..Default::default()
+	};
+	let (network, received) = network(codec, stream_config);
+	let connection = network.connect(peer(local)).await.unwrap();
+	(network, received, connection)
+}
+
+fn peer(local: bool) -> Peer {
+	match local {
+		true => Peer::Server,
+		false => Peer::Client(0),
+	}
+}
+
+async fn round_trip_uni(codec: Format, local: bool) {
+	use socknet::stream::{
+		handler::Initiator,
+		kind::{Send, Write},
+	};
+	let (_network, received, connection) = connect(codec, local).await;
+	let mut handler = uni::Initiator::<Payload>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler.0.stream.write(&Payload::sample()).await.unwrap();
+	handler.0.stream.finish().await.unwrap();
+	received.assert_next(&Payload::sample()).await;
+}
+
+async fn round_trip_bi(codec: Format, local: bool) {
+	use socknet::stream::{
+		handler::Initiator,
+		kind::{Read, Write},
+	};
+	let (_network, received, connection) = connect(codec, local).await;
+	let mut handler = bi::Handler::<Payload>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler.0.stream.write(&Payload::sample()).await.unwrap();
+	received.assert_next(&Payload::sample()).await;
+	let echo = tokio::time::timeout(testing::TIMEOUT, handler.0.stream.read::<Payload>())
+		.await
+		.expect("timed out waiting for echo")
+		.unwrap();
+	assert_eq!(echo, Payload::sample());
+}
+
+async fn round_trip_datagram(codec: Format, local: bool) {
+	use socknet::stream::{
+		handler::Initiator,
+		kind::{Send, Write},
+	};
+	let (_network, received, connection) = connect(codec, local).await;
+	let mut handler = datagram::Initiator::<Payload>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler.0.stream.write(&Payload::sample()).await.unwrap();
+	handler.0.stream.finish().await.unwrap();
+	received.assert_next(&Payload::sample()).await;
+}
+
+#[tokio::test]
+async fn codec_encodings_round_trip() {
+	for codec in Format::all() {
+		use socknet::stream::codec::Codec;
+		let bytes = codec.encode(&Payload::sample()).unwrap();
+		assert_eq!(codec.decode::<Payload>(&bytes).unwrap(), Payload::sample());
+	}
+}
+
+#[tokio::test]
+async fn uni_remote() {
+	for codec in Format::all() {
+		round_trip_uni(codec, false).await;
+	}
+}
+
+#[tokio::test]
+async fn bi_remote() {
+	for codec in Format::all() {
+		round_trip_bi(codec, false).await;
+	}
+}
+
+#[tokio::test]
+async fn datagram_remote() {
+	for codec in Format::all() {
+		round_trip_datagram(codec, false).await;
+	}
+}
+
+#[tokio::test]
+async fn uni_local() {
+	for codec in Format::all() {
+		round_trip_uni(codec, true).await;
+	}
+}
+
+#[tokio::test]
+async fn bi_local() {
+	for codec in Format::all() {
+		round_trip_bi(codec, true).await;
+	}
+}
+
+#[tokio::test]
+async fn datagram_local() {
+	for codec in Format::all() {
+		round_trip_datagram(codec, true).await;
+	}
+}
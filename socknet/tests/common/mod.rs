@@ -0,0 +1,276 @@
+//! Handlers shared by the integration tests, which report what they receive to the test.
+#![allow(dead_code)]
+
+use socknet::{
+	stream::{self, codec::Format},
+	testing::{self, Messages, Network, Peer},
+};
+use std::sync::Arc;
+
+/// A value which the handlers can send and receive.
+pub trait Message:
+	serde::Serialize + serde::de::DeserializeOwned + Clone + Send + Sync + 'static
+{
+}
+impl<T> Message for T where
+	T: serde::Serialize + serde::de::DeserializeOwned + Clone + Send + Sync + 'static
+{
+}
+
+/// Shared state for the handlers of a given endpoint.
+pub struct AppContext<T: Message> {
+	/// The codec the handlers configure their streams with, instead of the endpoint's.
+	pub codec: Option<Format>,
+	/// Reports every message the handlers receive.
+	pub received: async_channel::Sender<T>,
+	/// When set, unidirectional streams are handed to the test without being read,
+	/// so the test controls when items are consumed.
+	pub streams: Option<async_channel::Sender<stream::recv::Context<AppContext<T>>>>,
+	/// When set, bidirectional streams are handed to the test without being echoed.
+	pub echoes: Option<async_channel::Sender<bi::Handler<T>>>,
+}
+
+impl<T: Message> AppContext<T> {
+	pub fn new(received: async_channel::Sender<T>) -> Self {
+		Self {
+			codec: None,
+			received,
+			streams: None,
+			echoes: None,
+		}
+	}
+
+	fn configure(&self, config: &mut stream::Config) {
+		if let Some(codec) = self.codec {
+			config.codec = codec;
+		}
+	}
+}
+
+pub mod uni {
+	use super::*;
+
+	pub struct Identifier<T: Message>(pub Arc<AppContext<T>>);
+	impl<T: Message> stream::Identifier for Identifier<T> {
+		type SendBuilder = AppContext<T>;
+		type RecvBuilder = AppContext<T>;
+		fn unique_id() -> &'static str {
+			"test/uni"
+		}
+		fn send_builder(&self) -> &Arc<Self::SendBuilder> {
+			&self.0
+		}
+		fn recv_builder(&self) -> &Arc<Self::RecvBuilder> {
+			&self.0
+		}
+	}
+
+	impl<T: Message> stream::send::AppContext for AppContext<T> {
+		type Opener = stream::uni::Opener;
+		fn configure(&self, config: &mut stream::Config) {
+			AppContext::configure(self, config);
+		}
+	}
+
+	impl<T: Message> stream::recv::AppContext for AppContext<T> {
+		type Extractor = stream::uni::Extractor;
+		type Receiver = Receiver<T>;
+		fn configure(&self, config: &mut stream::Config) {
+			AppContext::configure(self, config);
+		}
+	}
+
+	pub struct Initiator<T: Message>(pub stream::send::Context<AppContext<T>>);
+	impl<T: Message> stream::handler::Initiator for Initiator<T> {
+		type Identifier = Identifier<T>;
+	}
+	impl<T: Message> From<stream::send::Context<AppContext<T>>> for Initiator<T> {
+		fn from(context: stream::send::Context<AppContext<T>>) -> Self {
+			Self(context)
+		}
+	}
+
+	/// Reads messages until the stream ends, or hands the stream to the test if it [`holds streams`](AppContext::streams).
+	pub struct Receiver<T: Message>(stream::recv::Context<AppContext<T>>);
+	impl<T: Message> From<stream::recv::Context<AppContext<T>>> for Receiver<T> {
+		fn from(context: stream::recv::Context<AppContext<T>>) -> Self {
+			Self(context)
+		}
+	}
+	impl<T: Message> stream::handler::Receiver for Receiver<T> {
+		type Identifier = Identifier<T>;
+		fn receive(mut self) {
+			tokio::task::spawn(async move {
+				use stream::kind::Read;
+				if let Some(streams) = self.0.builder.streams.clone() {
+					streams.send(self.0).await.unwrap();
+					return;
+				}
+				while let Ok(message) = self.0.stream.read::<T>().await {
+					self.0.builder.received.send(message).await.unwrap();
+				}
+			});
+		}
+	}
+}
+
+pub mod bi {
+	use super::*;
+
+	pub struct Identifier<T: Message>(pub Arc<Echo<T>>);
+	impl<T: Message> stream::Identifier for Identifier<T> {
+		type SendBuilder = Echo<T>;
+		type RecvBuilder = Echo<T>;
+		fn unique_id() -> &'static str {
+			"test/bi"
+		}
+		fn send_builder(&self) -> &Arc<Self::SendBuilder> {
+			&self.0
+		}
+		fn recv_builder(&self) -> &Arc<Self::RecvBuilder> {
+			&self.0
+		}
+	}
+
+	/// Wraps the shared app context, so the bidirectional handler can have its own trait impls.
+	pub struct Echo<T: Message>(pub Arc<AppContext<T>>);
+
+	impl<T: Message> stream::send::AppContext for Echo<T> {
+		type Opener = stream::bi::Opener;
+		fn configure(&self, config: &mut stream::Config) {
+			self.0.configure(config);
+		}
+	}
+
+	impl<T: Message> stream::recv::AppContext for Echo<T> {
+		type Extractor = stream::bi::Extractor;
+		type Receiver = Handler<T>;
+		fn configure(&self, config: &mut stream::Config) {
+			self.0.configure(config);
+		}
+	}
+
+	/// Echoes every message back to the peer until the stream ends,
+	/// or hands the stream to the test if it [`holds echoes`](AppContext::echoes).
+	pub struct Handler<T: Message>(pub stream::Context<Echo<T>, stream::kind::Bidirectional>);
+	impl<T: Message> From<stream::Context<Echo<T>, stream::kind::Bidirectional>> for Handler<T> {
+		fn from(context: stream::Context<Echo<T>, stream::kind::Bidirectional>) -> Self {
+			Self(context)
+		}
+	}
+	impl<T: Message> stream::handler::Initiator for Handler<T> {
+		type Identifier = Identifier<T>;
+	}
+	impl<T: Message> stream::handler::Receiver for Handler<T> {
+		type Identifier = Identifier<T>;
+		fn receive(mut self) {
+			tokio::task::spawn(async move {
+				use stream::kind::{Read, Write};
+				if let Some(echoes) = self.0.builder.0.echoes.clone() {
+					echoes.send(self).await.unwrap();
+					return;
+				}
+				while let Ok(message) = self.0.stream.read::<T>().await {
+					self.0
+						.builder
+						.0
+						.received
+						.send(message.clone())
+						.await
+						.unwrap();
+					if self.0.stream.write(&message).await.is_err() {
+						break;
+					}
+				}
+			});
+		}
+	}
+}
+
+pub mod datagram {
+	use super::*;
+
+	pub struct Identifier<T: Message>(pub Arc<Buffer<T>>);
+	impl<T: Message> stream::Identifier for Identifier<T> {
+		type SendBuilder = Buffer<T>;
+		type RecvBuilder = Buffer<T>;
+		fn unique_id() -> &'static str {
+			"test/datagram"
+		}
+		fn send_builder(&self) -> &Arc<Self::SendBuilder> {
+			&self.0
+		}
+		fn recv_builder(&self) -> &Arc<Self::RecvBuilder> {
+			&self.0
+		}
+	}
+
+	/// Wraps the shared app context, so the datagram handler can have its own trait impls.
+	pub struct Buffer<T: Message>(pub Arc<AppContext<T>>);
+
+	impl<T: Message> stream::send::AppContext for Buffer<T> {
+		type Opener = stream::datagram::Opener;
+		fn configure(&self, config: &mut stream::Config) {
+			self.0.configure(config);
+		}
+	}
+
+	impl<T: Message> stream::recv::AppContext for Buffer<T> {
+		type Extractor = stream::datagram::Extractor;
+		type Receiver = Receiver<T>;
+		fn configure(&self, config: &mut stream::Config) {
+			self.0.configure(config);
+		}
+	}
+
+	pub struct Initiator<T: Message>(pub stream::send::Context<Buffer<T>>);
+	impl<T: Message> stream::handler::Initiator for Initiator<T> {
+		type Identifier = Identifier<T>;
+	}
+	impl<T: Message> From<stream::send::Context<Buffer<T>>> for Initiator<T> {
+		fn from(context: stream::send::Context<Buffer<T>>) -> Self {
+			Self(context)
+		}
+	}
+
+	/// Reads the single message of the datagram.
+	pub struct Receiver<T: Message>(stream::recv::Context<Buffer<T>>);
+	impl<T: Message> From<stream::recv::Context<Buffer<T>>> for Receiver<T> {
+		fn from(context: stream::recv::Context<Buffer<T>>) -> Self {
+			Self(context)
+		}
+	}
+	impl<T: Message> stream::handler::Receiver for Receiver<T> {
+		type Identifier = Identifier<T>;
+		fn receive(mut self) {
+			tokio::task::spawn(async move {
+				use stream::kind::Read;
+				if let Ok(message) = self.0.stream.read::<T>().await {
+					self.0.builder.0.received.send(message).await.unwrap();
+				}
+			});
+		}
+	}
+}
+
+/// Creates a registry with every handler, all sharing the same context.
+pub fn registry<T: Message>(context: Arc<AppContext<T>>) -> stream::Registry {
+	let registry = stream::Registry::default();
+	registry.register(uni::Identifier(context.clone()));
+	registry.register(bi::Identifier(Arc::new(bi::Echo(context.clone()))));
+	registry.register(datagram::Identifier(Arc::new(datagram::Buffer(context))));
+	registry
+}
+
+/// Creates a network whose endpoints report every message they receive.
+pub fn network<T: Message>(
+	clients: usize,
+	stream_config: stream::Config,
+) -> (Network, Messages<T>) {
+	let (received, messages) = testing::messages();
+	let network = Network::new(clients, stream_config, |_peer: Peer| {
+		registry(Arc::new(AppContext::new(received.clone())))
+	})
+	.unwrap();
+	(network, messages)
+}
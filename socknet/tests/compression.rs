@@ -0,0 +1,179 @@
+//! Compressed messages over remote streams, and the compressed input which readers must reject.
+#![cfg(all(
+	feature = "testing",
+	any(feature = "compression-zstd", feature = "compression-lz4")
+))]
+
+mod common;
+
+use common::uni;
+use socknet::{
+	stream::{
+		self,
+		capture::{self, Event, Recorder},
+		compression::{self, Algorithm, Compression},
+		framing::Framing,
+		kind::{recv, Configure, Read},
+	},
+	testing::Peer,
+};
+use std::sync::{Arc, Mutex};
+
+/// A capture output which the test can read while the recorder is still in use.
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl std::io::Write for Output {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		self.0.lock().unwrap().extend_from_slice(buf);
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		Ok(())
+	}
+}
+
+fn algorithms() -> Vec<Algorithm> {
+	vec![
+		#[cfg(feature = "compression-zstd")]
+		Algorithm::Zstd { level: 3 },
+		#[cfg(feature = "compression-lz4")]
+		Algorithm::Lz4,
+	]
+}
+
+/// Sends a message over a stream, returning the message as it was sent (without its size header).
+async fn round_trip(algorithm: Algorithm, peer: Peer, message: String) -> Vec<u8> {
+	use stream::{
+		handler::Initiator,
+		kind::{Send, Write},
+	};
+	let stream_config = stream::Config {
+		compression: Some(Compression::new(algorithm)),
+		// Local messages are only recorded when they are serialized.
+		serialize_local: true,
+		..Default::default()
+	};
+	let (network, received) = common::network::<String>(1, stream_config);
+	let output = Output::default();
+	network
+		.endpoint(peer)
+		.set_recorder(Some(Recorder::new(output.clone())));
+	let connection = network.connect(peer).await.unwrap();
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler.0.stream.write(&message).await.unwrap();
+	handler.0.stream.finish().await.unwrap();
+	received.assert_next(&message).await;
+
+	let records = capture::read(&output.0.lock().unwrap()[..]).unwrap();
+	let sent = records
+		.into_iter()
+		.filter_map(|record| match record.event {
+			Event::Sent(framed) => Some(framed),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+	// The handler id is always sent uncompressed, before the configuration of the handler is applied.
+	assert_eq!(sent.len(), 2);
+	let framed = &sent[1];
+	framed[Framing::default().header_len(framed[0])..].to_vec()
+}
+
+#[tokio::test]
+async fn messages_below_the_threshold_are_not_compressed() {
+	for algorithm in algorithms() {
+		let sent = round_trip(algorithm, Peer::Client(0), "small".to_owned()).await;
+		assert_eq!(sent[0], 0);
+	}
+}
+
+#[tokio::test]
+async fn messages_above_the_threshold_are_compressed() {
+	for algorithm in algorithms() {
+		let message = "compressible ".repeat(Compression::DEFAULT_THRESHOLD);
+		let sent = round_trip(algorithm, Peer::Client(0), message.clone()).await;
+		assert_ne!(sent[0], 0);
+		assert!(sent.len() < message.len());
+	}
+}
+
+#[tokio::test]
+async fn local_messages_are_never_compressed() {
+	use stream::codec::{Bincode, Codec};
+	for algorithm in algorithms() {
+		let message = "compressible ".repeat(Compression::DEFAULT_THRESHOLD);
+		let sent = round_trip(algorithm, Peer::Server, message.clone()).await;
+		// Not even a compression header is written.
+		assert_eq!(sent, Bincode.encode(&message).unwrap());
+	}
+}
+
+/// Frames a message with its size header, like the remote writers do.
+fn frame(message: &[u8]) -> Vec<u8> {
+	let mut bytes = Framing::default().encode(message.len()).unwrap();
+	bytes.extend_from_slice(message);
+	bytes
+}
+
+/// Reads the bytes of a single compressed message from a datagram.
+async fn read_compressed(header: u8, message: &[u8], max_message_size: usize) -> anyhow::Error {
+	let mut compressed = vec![header];
+	compressed.extend_from_slice(message);
+	let mut datagram = recv::Datagram::from(bytes::Bytes::from(frame(&compressed)));
+	datagram.configure(&stream::Config {
+		compression: algorithms().first().copied().map(Compression::new),
+		max_message_size,
+		..Default::default()
+	});
+	datagram.read_bytes().await.unwrap_err()
+}
+
+#[tokio::test]
+async fn unknown_compression_headers_are_rejected() {
+	let error = read_compressed(0xff, b"data", 1024).await;
+	let source = match error.downcast_ref::<recv::Error>() {
+		Some(recv::Error::Malformed(source)) => source,
+		_ => panic!("expected the message to be malformed, but got {:?}", error),
+	};
+	assert_eq!(
+		source.to_string(),
+		compression::Error::UnsupportedHeader(0xff).to_string()
+	);
+}
+
+fn assert_too_large(error: anyhow::Error, limit: usize) {
+	match error.downcast_ref::<recv::Error>() {
+		Some(recv::Error::MessageTooLarge {
+			size,
+			limit: actual,
+		}) => {
+			assert!(*size > limit);
+			assert_eq!(*actual, limit);
+		}
+		_ => panic!("expected the message to be too large, but got {:?}", error),
+	}
+}
+
+#[cfg(feature = "compression-zstd")]
+#[tokio::test]
+async fn zstd_bombs_hit_the_limit() {
+	let bomb = zstd::bulk::compress(&vec![0u8; 16 << 20], 3).unwrap();
+	assert!(bomb.len() < 1024);
+	assert_too_large(read_compressed(1, &bomb, 1 << 20).await, 1 << 20);
+}
+
+#[cfg(feature = "compression-lz4")]
+#[tokio::test]
+async fn lz4_bombs_hit_the_limit() {
+	let bomb = lz4_flex::compress_prepend_size(&vec![0u8; 16 << 20]);
+	assert_too_large(read_compressed(2, &bomb, 1 << 20).await, 1 << 20);
+
+	// The decompressed size is checked before any of the message is decompressed.
+	let mut forged = (u32::MAX).to_le_bytes().to_vec();
+	forged.extend_from_slice(b"tiny");
+	assert_too_large(read_compressed(2, &forged, 1 << 20).await, 1 << 20);
+}
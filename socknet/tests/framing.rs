@@ -0,0 +1,61 @@
+use socknet::stream::framing::Framing;
+
+fn round_trip(framing: Framing, size: usize) -> usize {
+	let header = framing.encode(size).unwrap();
+	assert_eq!(framing.header_len(header[0]), header.len());
+	framing.decode(&header).unwrap()
+}
+
+#[test]
+fn varint_boundaries() {
+	let cases: &[(usize, usize)] = &[
+		(0, 1),
+		(63, 1),
+		(64, 2),
+		(16383, 2),
+		(16384, 4),
+		((1 << 30) - 1, 4),
+		(1 << 30, 8),
+		(Framing::MAX_VARINT as usize, 8),
+	];
+	for (size, header_len) in cases.iter().copied() {
+		assert_eq!(Framing::Varint.encode(size).unwrap().len(), header_len);
+		assert_eq!(round_trip(Framing::Varint, size), size);
+	}
+}
+
+#[test]
+fn varint_matches_rfc_examples() {
+	// Examples from RFC 9000, appendix A.1
+	assert_eq!(Framing::Varint.encode(37).unwrap(), vec![0x25]);
+	assert_eq!(Framing::Varint.encode(15293).unwrap(), vec![0x7b, 0xbd]);
+	assert_eq!(
+		Framing::Varint.encode(494878333).unwrap(),
+		vec![0x9d, 0x7f, 0x3e, 0x7d]
+	);
+}
+
+#[test]
+fn varint_rejects_unencodable_sizes() {
+	assert!(Framing::Varint.encode(usize::MAX).is_err());
+}
+
+#[test]
+fn fixed32_is_compatible() {
+	assert_eq!(
+		Framing::Fixed32.encode(300).unwrap(),
+		bincode::serialize(&300u32).unwrap()
+	);
+	assert_eq!(
+		round_trip(Framing::Fixed32, u32::MAX as usize),
+		u32::MAX as usize
+	);
+	assert!(Framing::Fixed32.encode(u32::MAX as usize + 1).is_err());
+}
+
+#[test]
+fn decode_rejects_truncated_headers() {
+	assert!(Framing::Varint.decode(&[]).is_err());
+	assert!(Framing::Varint.decode(&[0x7b]).is_err());
+	assert!(Framing::Fixed32.decode(&[1, 0]).is_err());
+}
@@ -0,0 +1,638 @@
+//! Behavior specific to local connections (an endpoint connected to itself).
+#![cfg(feature = "testing")]
+
+mod common;
+
+use common::{datagram, uni, AppContext};
+use socknet::{
+	connection::{CloseCode, Connection, LocalConfig},
+	endpoint::Endpoint,
+	stream,
+	testing::{Network, Peer},
+};
+use std::{
+	sync::{Arc, Weak},
+	time::Duration,
+};
+
+/// A server (and a client) whose handlers hand their unidirectional streams to the test without reading them,
+/// so the test controls when items are consumed.
+struct Host {
+	network: Network,
+	endpoint: Arc<Endpoint>,
+	streams: async_channel::Receiver<stream::recv::Context<AppContext<u32>>>,
+	datagrams: async_channel::Receiver<u32>,
+}
+
+fn host(local_config: LocalConfig) -> Host {
+	host_with(stream::Config::default(), local_config)
+}
+
+fn host_with(stream_config: stream::Config, local_config: LocalConfig) -> Host {
+	let (streams, stream_receiver) = async_channel::unbounded();
+	let (datagrams, datagram_receiver) = async_channel::unbounded();
+	let network = Network::with_local_config(1, stream_config, local_config, |_peer| {
+		let mut context = AppContext::new(datagrams.clone());
+		context.streams = Some(streams.clone());
+		common::registry(Arc::new(context))
+	})
+	.unwrap();
+	Host {
+		endpoint: network.server.clone(),
+		network,
+		streams: stream_receiver,
+		datagrams: datagram_receiver,
+	}
+}
+
+impl Host {
+	async fn connect(&self) -> Weak<Connection> {
+		self.network.connect(Peer::Server).await.unwrap()
+	}
+}
+
+#[tokio::test]
+async fn stream_writes_wait_for_reader() {
+	use stream::{
+		handler::Initiator,
+		kind::{Read, Write},
+	};
+	let host = host(LocalConfig {
+		stream_capacity: 4,
+		..Default::default()
+	});
+	let connection = host.connect().await;
+	let mut handler = uni::Initiator::<u32>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	let mut receiver = tokio::time::timeout(Duration::from_secs(5), host.streams.recv())
+		.await
+		.expect("timed out waiting for stream")
+		.unwrap();
+
+	// The queue can be filled without waiting on the reader.
+	for value in 0..4u32 {
+		handler.0.stream.write(&value).await.unwrap();
+	}
+	let blocked = tokio::time::timeout(Duration::from_millis(100), handler.0.stream.write(&4u32));
+	assert!(
+		blocked.await.is_err(),
+		"write should wait while the queue is full"
+	);
+
+	// Reading a value makes room for the next write.
+	assert_eq!(receiver.stream.read::<u32>().await.unwrap(), 0);
+	tokio::time::timeout(Duration::from_secs(5), handler.0.stream.write(&4u32))
+		.await
+		.expect("write should complete once the queue has room")
+		.unwrap();
+	for value in 1..=4u32 {
+		assert_eq!(receiver.stream.read::<u32>().await.unwrap(), value);
+	}
+}
+
+#[tokio::test]
+async fn datagrams_drop_when_full() {
+	use stream::{
+		handler::Initiator,
+		kind::{Send, Write},
+	};
+	let host = host(LocalConfig {
+		datagram_capacity: 2,
+		..Default::default()
+	});
+	let connection = host.connect().await;
+
+	// Without yielding to the runtime, nothing drains the queue while datagrams are sent.
+	let connection = connection.upgrade().unwrap();
+	for value in 0..10u32 {
+		let mut handler = datagram::Initiator::<u32>::open(&Arc::downgrade(&connection))
+			.unwrap()
+			.await
+			.unwrap();
+		handler.0.stream.write(&value).await.unwrap();
+		handler.0.stream.finish().await.unwrap();
+	}
+
+	let mut received = Vec::new();
+	while let Ok(Ok(value)) =
+		tokio::time::timeout(Duration::from_millis(200), host.datagrams.recv()).await
+	{
+		received.push(value);
+	}
+	received.sort_unstable();
+	assert_eq!(received, vec![0, 1]);
+}
+
+fn closed_error(error: &anyhow::Error) -> Option<(CloseCode, String)> {
+	match error.downcast_ref::<socknet::connection::Error>() {
+		Some(socknet::connection::Error::ConnectionClosed { code, reason }) => {
+			Some((*code, reason.clone()))
+		}
+		_ => None,
+	}
+}
+
+#[tokio::test]
+async fn close_fails_pending_reads_and_writes() {
+	use socknet::connection::Active;
+	use stream::{
+		handler::Initiator,
+		kind::{Read, Write},
+	};
+	let host = host(LocalConfig {
+		stream_capacity: 1,
+		..Default::default()
+	});
+	let connection = host.connect().await;
+	let mut handler = uni::Initiator::<u32>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	let mut receiver = tokio::time::timeout(Duration::from_secs(5), host.streams.recv())
+		.await
+		.expect("timed out waiting for stream")
+		.unwrap();
+
+	let pending_read = tokio::task::spawn(async move { receiver.stream.read::<u32>().await });
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	Connection::upgrade(&connection)
+		.unwrap()
+		.close(CloseCode::Application(7), "host left");
+
+	let error = tokio::time::timeout(Duration::from_secs(5), pending_read)
+		.await
+		.expect("pending read should end when the connection closes")
+		.unwrap()
+		.unwrap_err();
+	assert_eq!(
+		closed_error(&error),
+		Some((CloseCode::Application(7), "host left".to_owned()))
+	);
+
+	let error = handler.0.stream.write(&0u32).await.unwrap_err();
+	assert_eq!(
+		closed_error(&error),
+		Some((CloseCode::Application(7), "host left".to_owned()))
+	);
+
+	let error = match uni::Initiator::<u32>::open(&connection).unwrap().await {
+		Ok(_) => panic!("streams cannot be opened on a closed connection"),
+		Err(error) => error,
+	};
+	assert_eq!(
+		closed_error(&error),
+		Some((CloseCode::Application(7), "host left".to_owned()))
+	);
+}
+
+#[tokio::test]
+async fn close_drops_connection() {
+	use socknet::connection::{event::Event, Active};
+	let host = host(LocalConfig::default());
+	let connection = host.connect().await;
+	let address = host.endpoint.address();
+	let events = host.endpoint.connection_receiver();
+	match events.recv().await.unwrap() {
+		Event::Created(_) => {}
+		event => panic!("expected connection to be created, found {:?}", event),
+	}
+
+	Connection::upgrade(&connection)
+		.unwrap()
+		.close(CloseCode::Shutdown, "");
+	match tokio::time::timeout(Duration::from_secs(5), events.recv()).await {
+		Ok(Ok(Event::Closed(closed, _))) => assert_eq!(closed, address),
+		Ok(Ok(event)) => panic!("expected connection to be closed, found {:?}", event),
+		_ => panic!("timed out waiting for the connection to be closed"),
+	}
+	match tokio::time::timeout(Duration::from_secs(5), events.recv()).await {
+		Ok(Ok(Event::Dropped(dropped))) => assert_eq!(dropped, address),
+		Ok(Ok(event)) => panic!("expected connection to be dropped, found {:?}", event),
+		_ => panic!("timed out waiting for the connection to be dropped"),
+	}
+	assert!(connection.upgrade().is_none());
+}
+
+async fn created(endpoint: &Endpoint) -> Weak<Connection> {
+	use socknet::connection::event::Event;
+	match tokio::time::timeout(
+		Duration::from_secs(5),
+		endpoint.connection_receiver().recv(),
+	)
+	.await
+	{
+		Ok(Ok(Event::Created(connection))) => connection,
+		Ok(Ok(event)) => panic!("expected connection to be created, found {:?}", event),
+		_ => panic!("timed out waiting for the connection to be created"),
+	}
+}
+
+#[tokio::test]
+async fn in_memory_endpoints() {
+	use socknet::connection::Active;
+	use stream::{
+		handler::Initiator,
+		kind::{Read, Write},
+	};
+	let host = host(LocalConfig::default());
+	let (server, client) = (&host.network.server, &host.network.clients[0]);
+	let client_view = client.connect_in_memory(server);
+	assert!(created(client).await.ptr_eq(&client_view));
+	let server_view = created(server).await;
+
+	// Each side sees the identity of the other.
+	{
+		let client_view = Connection::upgrade(&client_view).unwrap();
+		let server_view = Connection::upgrade(&server_view).unwrap();
+		assert_eq!(client_view.remote_address(), server.address());
+		assert_eq!(server_view.remote_address(), client.address());
+		assert_eq!(client_view.certificate().unwrap(), *server.certificate());
+		assert_eq!(server_view.certificate().unwrap(), *client.certificate());
+	}
+
+	// Streams opened by each side are received by the other.
+	for (view, peer) in [(&client_view, server), (&server_view, client)] {
+		let mut handler = uni::Initiator::<u32>::open(view).unwrap().await.unwrap();
+		handler.0.stream.write(&42u32).await.unwrap();
+		let mut receiver = tokio::time::timeout(Duration::from_secs(5), host.streams.recv())
+			.await
+			.expect("timed out waiting for stream")
+			.unwrap();
+		assert_eq!(
+			receiver.connection.endpoint().unwrap().address(),
+			peer.address()
+		);
+		assert_eq!(receiver.stream.read::<u32>().await.unwrap(), 42);
+	}
+}
+
+/// The limits use the same clock as the rest of the runtime, so they can be tested with paused time.
+#[tokio::test(start_paused = true)]
+async fn queued_streams_wait_for_the_rate_in_paused_time() {
+	use stream::{handler::Initiator, kind::Write};
+	let host = host(LocalConfig::default());
+	host.endpoint
+		.stream_registry()
+		.limit_connections(stream::Limits {
+			rate: Some(stream::Rate {
+				per_second: 1.0,
+				burst: 1,
+			}),
+			overflow: stream::Overflow::Queue,
+			..Default::default()
+		});
+	let connection = host.connect().await;
+
+	let (start, real_start) = (tokio::time::Instant::now(), std::time::Instant::now());
+	for value in 0..2u32 {
+		let mut handler = uni::Initiator::<u32>::open(&connection)
+			.unwrap()
+			.await
+			.unwrap();
+		handler.0.stream.write(&value).await.unwrap();
+		host.streams.recv().await.unwrap();
+	}
+	// The second stream waited for a token, which only took time on the paused clock.
+	assert!(
+		start.elapsed() >= Duration::from_secs(1),
+		"{:?}",
+		start.elapsed()
+	);
+	assert!(
+		real_start.elapsed() < Duration::from_secs(1),
+		"{:?}",
+		real_start.elapsed()
+	);
+}
+
+#[cfg(feature = "simulator")]
+mod simulator {
+	use super::*;
+	use socknet::connection::simulator::{Conditions, Simulator};
+	use std::time::Instant;
+
+	async fn send_datagrams(connection: &Weak<Connection>, values: std::ops::Range<u32>) {
+		use stream::{
+			handler::Initiator,
+			kind::{Send, Write},
+		};
+		for value in values {
+			let mut handler = datagram::Initiator::<u32>::open(connection)
+				.unwrap()
+				.await
+				.unwrap();
+			handler.0.stream.write(&value).await.unwrap();
+			handler.0.stream.finish().await.unwrap();
+		}
+	}
+
+	async fn received(host: &Host) -> Vec<u32> {
+		let mut received = Vec::new();
+		while let Ok(Ok(value)) =
+			tokio::time::timeout(Duration::from_millis(200), host.datagrams.recv()).await
+		{
+			received.push(value);
+		}
+		received.sort_unstable();
+		received
+	}
+
+	fn simulate(stream_config: stream::Config, simulator: Simulator) -> Host {
+		let host = host_with(stream_config, LocalConfig::default());
+		host.endpoint.set_simulator(Some(simulator));
+		host
+	}
+
+	#[tokio::test]
+	async fn loss_is_seeded() {
+		let conditions = Conditions {
+			loss: 0.5,
+			..Default::default()
+		};
+		let mut runs = Vec::new();
+		for _ in 0..2 {
+			let host = simulate(
+				stream::Config::default(),
+				Simulator::new(7, conditions.clone()),
+			);
+			let connection = host.connect().await;
+			send_datagrams(&connection, 0..100).await;
+			runs.push(received(&host).await);
+		}
+		assert!(!runs[0].is_empty() && runs[0].len() < 100);
+		assert_eq!(runs[0], runs[1]);
+	}
+
+	#[tokio::test]
+	async fn duplication() {
+		let stream_config = stream::Config {
+			serialize_local: true,
+			..Default::default()
+		};
+		let simulator = Simulator::new(
+			0,
+			Conditions {
+				duplication: 1.0,
+				..Default::default()
+			},
+		);
+		let host = simulate(stream_config, simulator);
+		let connection = host.connect().await;
+		send_datagrams(&connection, 0..3).await;
+		assert_eq!(received(&host).await, vec![0, 0, 1, 1, 2, 2]);
+	}
+
+	#[tokio::test]
+	async fn latency_can_change_at_runtime() {
+		let simulator = Simulator::new(
+			0,
+			Conditions {
+				latency: Duration::from_millis(300),
+				..Default::default()
+			},
+		);
+		let host = simulate(stream::Config::default(), simulator.clone());
+		let connection = host.connect().await;
+
+		let start = Instant::now();
+		send_datagrams(&connection, 0..1).await;
+		host.datagrams.recv().await.unwrap();
+		assert!(start.elapsed() >= Duration::from_millis(300));
+
+		simulator.set_conditions(Conditions::default());
+		let start = Instant::now();
+		send_datagrams(&connection, 1..2).await;
+		host.datagrams.recv().await.unwrap();
+		assert!(start.elapsed() < Duration::from_millis(250));
+	}
+
+	/// Measures how long a value written to an open stream takes to be read.
+	async fn stream_write_delay(
+		simulator: &Simulator,
+		conditions: Conditions,
+		value: &str,
+	) -> Duration {
+		use stream::{
+			handler::Initiator,
+			kind::{Read, Write},
+		};
+		let stream_config = stream::Config {
+			serialize_local: true,
+			..Default::default()
+		};
+		let host = simulate(stream_config, simulator.clone());
+		let connection = host.connect().await;
+		let mut handler = uni::Initiator::<u32>::open(&connection)
+			.unwrap()
+			.await
+			.unwrap();
+		handler.0.stream.write(&0u32).await.unwrap();
+		let mut receiver = host.streams.recv().await.unwrap();
+		assert_eq!(receiver.stream.read::<u32>().await.unwrap(), 0);
+
+		// The conditions only change once the stream is open, so only the write is affected.
+		simulator.set_conditions(conditions);
+		let start = Instant::now();
+		let (written, read) = tokio::join!(
+			handler.0.stream.write_bytes(value.as_bytes()),
+			receiver.stream.read_bytes()
+		);
+		written.unwrap();
+		assert_eq!(read.unwrap(), value.as_bytes());
+		start.elapsed()
+	}
+
+	#[tokio::test]
+	async fn stream_writes_are_delayed_by_latency() {
+		let simulator = Simulator::new(0, Conditions::default());
+		let conditions = Conditions {
+			latency: Duration::from_millis(300),
+			..Default::default()
+		};
+		let elapsed = stream_write_delay(&simulator, conditions, "late").await;
+		// The size header and the bytes of the message are delayed together, not one after another.
+		assert!(
+			elapsed >= Duration::from_millis(300) && elapsed < Duration::from_millis(600),
+			"{:?}",
+			elapsed
+		);
+	}
+
+	#[tokio::test]
+	async fn stream_writes_in_a_row_are_delayed_once() {
+		use stream::{
+			handler::Initiator as _,
+			kind::{Read, Write},
+		};
+		let simulator = Simulator::new(0, Conditions::default());
+		let stream_config = stream::Config {
+			serialize_local: true,
+			..Default::default()
+		};
+		let host = simulate(stream_config, simulator.clone());
+		let connection = host.connect().await;
+		let mut handler = uni::Initiator::<u32>::open(&connection)
+			.unwrap()
+			.await
+			.unwrap();
+		handler.0.stream.write(&0u32).await.unwrap();
+		let mut receiver = host.streams.recv().await.unwrap();
+		assert_eq!(receiver.stream.read::<u32>().await.unwrap(), 0);
+
+		simulator.set_conditions(Conditions {
+			latency: Duration::from_millis(300),
+			..Default::default()
+		});
+		let start = Instant::now();
+		// The writer is not held back by the latency, only the arrival of the messages.
+		for value in 1..=5u32 {
+			handler.0.stream.write(&value).await.unwrap();
+		}
+		assert!(
+			start.elapsed() < Duration::from_millis(300),
+			"{:?}",
+			start.elapsed()
+		);
+		for value in 1..=5u32 {
+			assert_eq!(receiver.stream.read::<u32>().await.unwrap(), value);
+		}
+		let elapsed = start.elapsed();
+		assert!(
+			elapsed >= Duration::from_millis(300) && elapsed < Duration::from_millis(600),
+			"{:?}",
+			elapsed
+		);
+	}
+
+	#[tokio::test]
+	async fn stream_writes_are_throttled_by_bandwidth() {
+		let simulator = Simulator::new(0, Conditions::default());
+		let conditions = Conditions {
+			bandwidth: Some(1000),
+			..Default::default()
+		};
+		let elapsed = stream_write_delay(&simulator, conditions, &"x".repeat(500)).await;
+		assert!(elapsed >= Duration::from_millis(450), "{:?}", elapsed);
+	}
+}
+
+mod capture {
+	use super::*;
+	use socknet::stream::capture::{self, Event, Initiator, Kind, Recorder};
+
+	fn serialized() -> Host {
+		let stream_config = stream::Config {
+			serialize_local: true,
+			..Default::default()
+		};
+		host_with(stream_config, LocalConfig::default())
+	}
+
+	#[tokio::test]
+	async fn record_and_replay() {
+		use stream::{
+			handler::Initiator as _,
+			kind::{Read, Send, Write},
+		};
+		let path = std::env::temp_dir().join(format!("socknet-capture-{}.bin", std::process::id()));
+
+		// Record a stream and a datagram sent by the endpoint to itself.
+		let recording = serialized();
+		recording
+			.endpoint
+			.set_recorder(Some(Recorder::create(&path).unwrap()));
+		let connection = recording.connect().await;
+		let mut handler = uni::Initiator::<u32>::open(&connection)
+			.unwrap()
+			.await
+			.unwrap();
+		handler.0.stream.write(&42u32).await.unwrap();
+		let mut receiver = recording.streams.recv().await.unwrap();
+		assert_eq!(receiver.stream.read::<u32>().await.unwrap(), 42);
+		let mut handler = datagram::Initiator::<u32>::open(&connection)
+			.unwrap()
+			.await
+			.unwrap();
+		handler.0.stream.write(&7u32).await.unwrap();
+		handler.0.stream.finish().await.unwrap();
+		assert_eq!(recording.datagrams.recv().await.unwrap(), 7);
+		recording.endpoint.recorder().unwrap().flush().unwrap();
+
+		let records = capture::read_file(&path).unwrap();
+		let _ = std::fs::remove_file(&path);
+		let opened = records
+			.iter()
+			.filter_map(|record| match &record.event {
+				Event::Opened {
+					kind, initiator, ..
+				} => Some((*kind, *initiator)),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(
+			opened,
+			vec![
+				(Kind::Unidirectional, Initiator::Local),
+				(Kind::Unidirectional, Initiator::Peer),
+				(Kind::Datagram, Initiator::Local),
+				(Kind::Datagram, Initiator::Peer),
+			]
+		);
+		let identified = records
+			.iter()
+			.filter_map(|record| match &record.event {
+				Event::Identified(id) => Some(id.as_str()),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(
+			identified,
+			vec!["test/uni", "test/uni", "test/datagram", "test/datagram"]
+		);
+		let count =
+			|matches: fn(&Event) -> bool| records.iter().filter(|r| matches(&r.event)).count();
+		// The handler id and value of each stream.
+		assert_eq!(count(|event| matches!(event, Event::Sent(_))), 4);
+		assert_eq!(count(|event| matches!(event, Event::Received(_))), 4);
+
+		// Replaying the capture into another endpoint receives the same values.
+		let replayed = host(LocalConfig::default());
+		capture::replay(&replayed.endpoint, &records);
+		let mut receiver = tokio::time::timeout(Duration::from_secs(5), replayed.streams.recv())
+			.await
+			.expect("timed out waiting for replayed stream")
+			.unwrap();
+		assert_eq!(receiver.stream.read::<u32>().await.unwrap(), 42);
+		let value = tokio::time::timeout(Duration::from_secs(5), replayed.datagrams.recv())
+			.await
+			.expect("timed out waiting for replayed datagram")
+			.unwrap();
+		assert_eq!(value, 7);
+	}
+
+	#[test]
+	fn truncated_and_oversized_records_are_rejected() {
+		use stream::framing::Framing;
+		let record = capture::Record {
+			stream: 0,
+			time: Duration::from_millis(5),
+			event: Event::Sent(vec![1, 2, 3]),
+		};
+		let bytes = bincode::serialize(&record).unwrap();
+		let mut capture = Framing::Varint.encode(bytes.len()).unwrap();
+		capture.extend_from_slice(&bytes);
+		let records = capture::read(&capture[..]).unwrap();
+		assert_eq!(records.len(), 1);
+		assert_eq!(records[0].event, record.event);
+
+		// The capture ends part way through the record.
+		assert!(capture::read(&capture[..capture.len() - 1]).is_err());
+
+		// The size header claims far more bytes than the capture has, which must not be allocated up front.
+		let mut oversized = Framing::Varint.encode(1 << 60).unwrap();
+		oversized.extend_from_slice(&bytes);
+		assert!(capture::read(&oversized[..]).is_err());
+	}
+}
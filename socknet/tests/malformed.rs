@@ -0,0 +1,154 @@
+//! Feeds random and truncated bytes through the stream readers,
+//! which must always fail with an error instead of panicking.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use socknet::stream::{
+	codec::{Bincode, Codec},
+	framing::Framing,
+	kind::{recv, Kind, Read},
+	local,
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Position {
+	entity: u64,
+	name: String,
+	coordinates: Vec<f32>,
+}
+
+const ITERATIONS: usize = 10_000;
+
+fn random_bytes(rng: &mut StdRng) -> bytes::Bytes {
+	let len = rng.gen_range(0..64);
+	(0..len)
+		.map(|_| rng.gen::<u8>())
+		.collect::<Vec<u8>>()
+		.into()
+}
+
+/// Frames a message the same way the remote writers do.
+fn frame(message: &[u8]) -> Vec<u8> {
+	let mut bytes = Framing::default().encode(message.len()).unwrap();
+	bytes.extend_from_slice(message);
+	bytes
+}
+
+fn valid_datagram() -> Vec<u8> {
+	let position = Position {
+		entity: 7,
+		name: "player".to_owned(),
+		coordinates: vec![1.0, -2.5, 3.25],
+	};
+	let mut bytes = frame(&Bincode.encode(&"position".to_owned()).unwrap());
+	bytes.extend(frame(&Bincode.encode(&position).unwrap()));
+	bytes
+}
+
+#[tokio::test]
+async fn random_handler_ids() {
+	let mut rng = StdRng::seed_from_u64(0x50c4e7);
+	for _ in 0..ITERATIONS {
+		let mut kind = Kind::from(random_bytes(&mut rng));
+		let _ = kind.read_handler_id().await;
+	}
+}
+
+#[tokio::test]
+async fn random_values() {
+	let mut rng = StdRng::seed_from_u64(0xda7a);
+	for _ in 0..ITERATIONS {
+		let mut datagram = recv::Datagram::from(random_bytes(&mut rng));
+		let _ = datagram.read::<Position>().await;
+		let _ = datagram.read::<String>().await;
+		let _ = datagram.read::<Vec<u64>>().await;
+	}
+}
+
+#[tokio::test]
+async fn truncated_datagrams() {
+	let bytes = valid_datagram();
+	for len in 0..bytes.len() {
+		let mut kind = Kind::from(bytes::Bytes::copy_from_slice(&bytes[..len]));
+		if kind.read_handler_id().await.is_ok() {
+			if let Kind::Datagram(datagram) = &mut kind {
+				assert!(datagram.read::<Position>().await.is_err());
+			}
+		}
+	}
+}
+
+#[tokio::test]
+async fn corrupted_datagrams() {
+	let mut rng = StdRng::seed_from_u64(0xc0ffee);
+	let valid = valid_datagram();
+	for _ in 0..ITERATIONS {
+		let mut bytes = valid.clone();
+		for _ in 0..rng.gen_range(1..4) {
+			let index = rng.gen_range(0..bytes.len());
+			bytes[index] = rng.gen();
+		}
+		let mut kind = Kind::from(bytes::Bytes::from(bytes));
+		if kind.read_handler_id().await.is_ok() {
+			if let Kind::Datagram(datagram) = &mut kind {
+				let _ = datagram.read::<Position>().await;
+			}
+		}
+	}
+}
+
+#[tokio::test]
+async fn valid_datagram_is_read() {
+	let mut kind = Kind::from(bytes::Bytes::from(valid_datagram()));
+	assert_eq!(kind.read_handler_id().await.unwrap(), "position");
+	if let Kind::Datagram(datagram) = &mut kind {
+		let position = datagram.read::<Position>().await.unwrap();
+		assert_eq!(position.name, "player");
+		assert!(matches!(
+			datagram
+				.read::<Position>()
+				.await
+				.unwrap_err()
+				.downcast::<recv::Error>(),
+			Ok(recv::Error::UnexpectedEof { .. })
+		));
+	}
+}
+
+#[tokio::test]
+async fn empty_local_datagram() {
+	let items: Vec<local::AnyBox> = Vec::new();
+	let mut kind = Kind::from(items);
+	assert!(kind.read_handler_id().await.is_err());
+}
+
+#[tokio::test]
+async fn mismatched_local_datagram() {
+	let items: Vec<local::AnyBox> =
+		vec![Box::new(3usize), Box::new(vec![1u8, 2, 3]), Box::new(5u32)];
+	let mut datagram = recv::Datagram::from(items);
+	match datagram
+		.read::<String>()
+		.await
+		.unwrap_err()
+		.downcast::<recv::Error>()
+	{
+		Ok(recv::Error::TypeMismatch { expected, actual }) => {
+			assert_eq!(expected, std::any::type_name::<String>());
+			assert_eq!(actual, std::any::type_name::<usize>());
+		}
+		other => panic!("unexpected result {:?}", other),
+	}
+	match datagram
+		.read_exact(4)
+		.await
+		.unwrap_err()
+		.downcast::<recv::Error>()
+	{
+		Ok(recv::Error::LengthMismatch { expected, actual }) => {
+			assert_eq!((expected, actual), (4, 3));
+		}
+		other => panic!("unexpected result {:?}", other),
+	}
+	assert_eq!(datagram.read::<u32>().await.unwrap(), 5);
+}
@@ -0,0 +1,155 @@
+//! Oversized messages and handler ids, which must be rejected from their size header alone
+//! (a reader which trusted the header would allocate the whole message before reading it).
+#![cfg(feature = "testing")]
+
+mod common;
+
+use common::uni;
+use socknet::{
+	connection::Active,
+	stream::{
+		self,
+		framing::Framing,
+		handler::Initiator,
+		kind::{code, recv, Configure, Kind, Read, StreamReset, Write},
+	},
+	testing::{self, Messages, Network, Peer},
+};
+
+const MAX_MESSAGE_SIZE: usize = 64;
+
+/// A size header which would take the whole address space to allocate.
+const HUGE: usize = 1 << 60;
+
+fn network() -> (Network, Messages<String>) {
+	common::network(
+		1,
+		stream::Config {
+			max_message_size: MAX_MESSAGE_SIZE,
+			serialize_local: true,
+			..Default::default()
+		},
+	)
+}
+
+/// Waits for the peer to stop a stream, returning the code it was stopped with.
+async fn rejection<W: Write + std::marker::Send>(stream: &mut W) -> u32 {
+	let rejected = tokio::time::timeout(testing::TIMEOUT, async {
+		loop {
+			if let Err(error) = stream.write(&"ignored".to_owned()).await {
+				return error;
+			}
+			tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+		}
+	})
+	.await
+	.expect("the stream was never stopped");
+	match rejected.downcast_ref::<StreamReset>() {
+		Some(reset) => reset.code,
+		_ => panic!("expected the stream to be stopped, but got {:?}", rejected),
+	}
+}
+
+async fn oversized_messages_are_rejected(peer: Peer) {
+	let (network, received) = network();
+	let connection = network.connect(peer).await.unwrap();
+
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler
+		.0
+		.stream
+		.write(&"x".repeat(MAX_MESSAGE_SIZE * 2))
+		.await
+		.unwrap();
+	assert_eq!(
+		rejection(&mut handler.0.stream).await,
+		code::PROTOCOL_VIOLATION
+	);
+
+	// Only the size header is sent, so the reader would wait forever if it tried to read the message.
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler.0.stream.write_size(HUGE).await.unwrap();
+	assert_eq!(
+		rejection(&mut handler.0.stream).await,
+		code::PROTOCOL_VIOLATION
+	);
+	received.assert_empty();
+}
+
+#[tokio::test]
+async fn oversized_messages_are_rejected_remote() {
+	oversized_messages_are_rejected(Peer::Client(0)).await;
+}
+
+#[tokio::test]
+async fn oversized_messages_are_rejected_local_serialized() {
+	oversized_messages_are_rejected(Peer::Server).await;
+}
+
+async fn oversized_handler_ids_are_rejected(peer: Peer) {
+	let (network, received) = network();
+	let connection = network.connect(peer).await.unwrap();
+	let connection = connection.upgrade().unwrap();
+
+	let mut stream = connection.open_uni().await.unwrap();
+	stream.configure(&connection.stream_config().unwrap());
+	stream
+		.write_size(stream::Config::DEFAULT_MAX_HANDLER_ID_SIZE + 1)
+		.await
+		.unwrap();
+	assert_eq!(rejection(&mut stream).await, code::PROTOCOL_VIOLATION);
+
+	let mut stream = connection.open_uni().await.unwrap();
+	stream.configure(&connection.stream_config().unwrap());
+	stream.write_size(HUGE).await.unwrap();
+	assert_eq!(rejection(&mut stream).await, code::PROTOCOL_VIOLATION);
+	received.assert_empty();
+}
+
+#[tokio::test]
+async fn oversized_handler_ids_are_rejected_remote() {
+	oversized_handler_ids_are_rejected(Peer::Client(0)).await;
+}
+
+#[tokio::test]
+async fn oversized_handler_ids_are_rejected_local_serialized() {
+	oversized_handler_ids_are_rejected(Peer::Server).await;
+}
+
+fn assert_too_large(error: anyhow::Error, expected_size: usize, expected_limit: usize) {
+	match error.downcast_ref::<recv::Error>() {
+		Some(recv::Error::MessageTooLarge { size, limit }) => {
+			assert_eq!((*size, *limit), (expected_size, expected_limit));
+		}
+		_ => panic!("expected the message to be too large, but got {:?}", error),
+	}
+}
+
+#[tokio::test]
+async fn oversized_datagrams_are_rejected() {
+	let header = Framing::default().encode(HUGE).unwrap();
+
+	let mut datagram = recv::Datagram::from(bytes::Bytes::from(header.clone()));
+	datagram.configure(&stream::Config {
+		max_message_size: MAX_MESSAGE_SIZE,
+		..Default::default()
+	});
+	assert_too_large(
+		datagram.read_bytes().await.unwrap_err(),
+		HUGE,
+		MAX_MESSAGE_SIZE,
+	);
+
+	let mut kind = Kind::from(bytes::Bytes::from(header));
+	assert_too_large(
+		kind.read_handler_id().await.unwrap_err(),
+		HUGE,
+		stream::Config::DEFAULT_MAX_HANDLER_ID_SIZE,
+	);
+}
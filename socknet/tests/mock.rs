@@ -0,0 +1,156 @@
+//! Unit testing a stream handler with a mock connection, without any endpoints.
+#![cfg(feature = "testing")]
+
+mod common;
+
+use common::{bi, AppContext};
+use socknet::{
+	connection::{Active, CloseCode},
+	stream::{
+		self,
+		kind::{Read, Send, Write},
+	},
+	testing::{self, Messages, MockConnection},
+};
+use std::sync::Arc;
+
+fn mock(
+	stream_config: stream::Config,
+) -> (MockConnection, Arc<bi::Echo<String>>, Messages<String>) {
+	let (received, messages) = testing::messages();
+	let echo = Arc::new(bi::Echo(Arc::new(AppContext::new(received))));
+	let registry = stream::Registry::default();
+	registry.register(bi::Identifier(echo.clone()));
+	let mock = MockConnection::new(Arc::new(registry), stream_config);
+	(mock, echo, messages)
+}
+
+async fn echo(stream_config: stream::Config) -> anyhow::Result<()> {
+	use stream::handler::Initiator;
+	let (mock, echo, received) = mock(stream_config);
+
+	let (mut send, mut recv) = mock.open_bi().await?;
+	send.write(&"hello".to_owned()).await?;
+	mock.receive(echo, mock.incoming().await?)?;
+	received.assert_next(&"hello".to_owned()).await;
+	assert_eq!(recv.read::<String>().await?, "hello");
+
+	// Streams opened on the connection under test are recorded for the peer.
+	let connection = Arc::downgrade(mock.connection());
+	let mut handler = bi::Handler::<String>::open(&connection)?.await?;
+	handler.0.stream.write(&"repeat".to_owned()).await?;
+	let mut opened = mock.opened().await?;
+	assert_eq!(opened.read_handler_id().await?, "test/bi");
+	let (_send, mut recv) = <stream::bi::Extractor as stream::Extractor>::extract(opened)?;
+	assert_eq!(recv.read::<String>().await?, "repeat");
+	Ok(())
+}
+
+#[tokio::test]
+async fn handler_writes_are_recorded() -> anyhow::Result<()> {
+	echo(stream::Config::default()).await
+}
+
+#[tokio::test]
+async fn handler_writes_are_recorded_serialized() -> anyhow::Result<()> {
+	echo(stream::Config {
+		serialize_local: true,
+		..Default::default()
+	})
+	.await
+}
+
+#[tokio::test]
+async fn incoming_datagrams() -> anyhow::Result<()> {
+	let mock = MockConnection::default();
+	let mut datagram = mock.open_datagram();
+	datagram.write(&42u32).await?;
+	datagram.finish().await?;
+	match mock.incoming().await? {
+		stream::kind::Kind::Datagram(mut recv) => assert_eq!(recv.read::<u32>().await?, 42),
+		_ => panic!("expected a datagram"),
+	}
+	Ok(())
+}
+
+#[tokio::test]
+async fn peer_closes_connection() -> anyhow::Result<()> {
+	let (mock, _echo, _received) = mock(stream::Config::default());
+	let (_send, mut recv) = mock.open_bi().await?;
+	assert_eq!(mock.close_reason(), None);
+	mock.peer().close(CloseCode::Application(7), "done");
+	assert_eq!(
+		mock.close_reason(),
+		Some((CloseCode::Application(7), "done".to_owned()))
+	);
+	assert!(recv.read::<String>().await.is_err());
+	assert!(mock.connection().open_uni().await.is_err());
+	Ok(())
+}
+
+#[tokio::test]
+async fn extensions_are_dropped_with_connection() {
+	#[derive(Clone, Debug, PartialEq)]
+	struct Account(Arc<String>);
+
+	let mock = MockConnection::default();
+	let account = Account(Arc::new("player".to_owned()));
+	let extensions = mock.connection().extensions();
+	assert_eq!(extensions.insert(account.clone()), None);
+	assert!(extensions.contains::<Account>());
+	assert_eq!(extensions.get::<Account>(), Some(account.clone()));
+	assert_eq!(extensions.insert(7u32), None);
+	extensions.with_mut(|count: &mut u32| *count += 1);
+	assert_eq!(extensions.remove::<u32>(), Some(8));
+	assert!(!extensions.contains::<u32>());
+
+	assert_eq!(Arc::strong_count(&account.0), 2);
+	drop(mock);
+	assert_eq!(Arc::strong_count(&account.0), 1);
+}
+
+#[tokio::test]
+async fn reset_and_stop_codes_reach_the_peer() -> anyhow::Result<()> {
+	use stream::kind::{code, Recv, StreamReset};
+
+	let mock = MockConnection::default();
+	let (mut peer_send, mut peer_recv) = mock.open_bi().await?;
+	let (mut send, mut recv) = match mock.incoming().await? {
+		stream::kind::Kind::Bidirectional(streams) => streams,
+		_ => panic!("expected a bidirectional stream"),
+	};
+
+	peer_send.write(&"first".to_owned()).await?;
+	assert_eq!(recv.read::<String>().await?, "first");
+	// The application's codes never collide with socknet's codes, such as TIMED_OUT.
+	peer_send.reset(code::application(6)).await?;
+	let reset = recv.read::<String>().await.unwrap_err();
+	let reset = reset.downcast_ref::<StreamReset>().unwrap();
+	assert_eq!(reset.code, code::APPLICATION_START + 6);
+	assert_eq!(reset.application(), Some(6));
+
+	peer_recv.stop(code::application(9)).await?;
+	let stopped = send.write(&"ignored".to_owned()).await.unwrap_err();
+	assert_eq!(
+		stopped.downcast_ref::<StreamReset>().unwrap().application(),
+		Some(9)
+	);
+	let finished = send.finish().await.unwrap_err();
+	assert_eq!(
+		finished
+			.downcast_ref::<StreamReset>()
+			.unwrap()
+			.application(),
+		Some(9)
+	);
+
+	// Codes below the application range are socknet's.
+	assert_eq!(
+		StreamReset {
+			code: code::TIMED_OUT
+		}
+		.application(),
+		None
+	);
+	Ok(())
+}
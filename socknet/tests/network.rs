@@ -0,0 +1,1048 @@
+//! Every kind of stream, over both remote and local connections, using the test network helpers.
+#![cfg(feature = "testing")]
+
+mod common;
+
+use common::{bi, datagram, uni, AppContext};
+use socknet::{
+	connection::Active,
+	stream::{
+		self,
+		handler::Initiator,
+		kind::{Read, Send, Write},
+	},
+	testing::{self, Messages, Network, Peer},
+};
+use std::sync::Arc;
+
+/// Creates a network whose server reports every message it receives.
+fn network(clients: usize, stream_config: stream::Config) -> (Network, Messages<String>) {
+	common::network(clients, stream_config)
+}
+
+const MESSAGES: [&str; 3] = ["first", "second", "third"];
+
+async fn uni(peer: Peer, stream_config: stream::Config) {
+	let (network, received) = network(1, stream_config);
+	let connection = network.connect(peer).await.unwrap();
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	for message in MESSAGES.iter() {
+		handler.0.stream.write(&message.to_string()).await.unwrap();
+	}
+	handler.0.stream.finish().await.unwrap();
+	for message in MESSAGES.iter() {
+		received.assert_next(&message.to_string()).await;
+	}
+	received.assert_empty();
+}
+
+async fn bi(peer: Peer, stream_config: stream::Config) {
+	let (network, received) = network(1, stream_config);
+	let connection = network.connect(peer).await.unwrap();
+	let mut handler = bi::Handler::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	for message in MESSAGES.iter() {
+		handler.0.stream.write(&message.to_string()).await.unwrap();
+		received.assert_next(&message.to_string()).await;
+		let echo = tokio::time::timeout(testing::TIMEOUT, handler.0.stream.read::<String>())
+			.await
+			.unwrap()
+			.unwrap();
+		assert_eq!(&echo, message);
+	}
+}
+
+async fn datagram(peer: Peer, stream_config: stream::Config) {
+	let (network, received) = network(1, stream_config);
+	let connection = network.connect(peer).await.unwrap();
+	let mut handler = datagram::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler
+		.0
+		.stream
+		.write(&MESSAGES[0].to_string())
+		.await
+		.unwrap();
+	handler.0.stream.finish().await.unwrap();
+	received.assert_next(&MESSAGES[0].to_string()).await;
+}
+
+#[tokio::test]
+async fn uni_remote() {
+	uni(Peer::Client(0), stream::Config::default()).await;
+}
+
+#[tokio::test]
+async fn uni_local() {
+	uni(Peer::Server, stream::Config::default()).await;
+}
+
+#[tokio::test]
+async fn uni_local_serialized() {
+	let stream_config = stream::Config {
+		serialize_local: true,
+		..Default::default()
+	};
+	uni(Peer::Server, stream_config).await;
+}
+
+#[tokio::test]
+async fn bi_remote() {
+	bi(Peer::Client(0), stream::Config::default()).await;
+}
+
+#[tokio::test]
+async fn bi_local() {
+	bi(Peer::Server, stream::Config::default()).await;
+}
+
+#[tokio::test]
+async fn datagram_remote() {
+	datagram(Peer::Client(0), stream::Config::default()).await;
+}
+
+#[tokio::test]
+async fn datagram_local() {
+	datagram(Peer::Server, stream::Config::default()).await;
+}
+
+#[tokio::test]
+async fn many_clients() {
+	let (network, received) = network(3, stream::Config::default());
+	for index in 0..network.clients.len() {
+		let connection = network.connect(Peer::Client(index)).await.unwrap();
+		let created = testing::wait_for_created(&network.server).await.unwrap();
+		let client = connection.upgrade().unwrap();
+		let server_side = created.upgrade().unwrap();
+		assert_eq!(
+			server_side.remote_address(),
+			network.clients[index].address()
+		);
+		assert_eq!(client.remote_address(), network.server.address());
+
+		let mut handler = uni::Initiator::<String>::open(&connection)
+			.unwrap()
+			.await
+			.unwrap();
+		handler.0.stream.write(&index.to_string()).await.unwrap();
+		handler.0.stream.finish().await.unwrap();
+		received.assert_next(&index.to_string()).await;
+	}
+	received.assert_empty();
+}
+
+/// Writes to a stream until the peer stops it, returning the code it was stopped with.
+async fn rejection<W: Write + std::marker::Send>(stream: &mut W) -> u32 {
+	let rejected = tokio::time::timeout(testing::TIMEOUT, async {
+		loop {
+			if let Err(error) = stream.write(&"ignored".to_owned()).await {
+				return error;
+			}
+			tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+		}
+	})
+	.await
+	.unwrap();
+	match rejected.downcast_ref::<stream::kind::StreamReset>() {
+		Some(reset) => reset.code,
+		_ => panic!("expected the stream to be stopped, but got {:?}", rejected),
+	}
+}
+
+#[tokio::test]
+async fn streams_outside_the_connection_registry_are_rejected() {
+	let (network, received) = network(1, stream::Config::default());
+	let connection = network.connect(Peer::Client(0)).await.unwrap();
+	let server_side = testing::wait_for_created(&network.server)
+		.await
+		.unwrap()
+		.upgrade()
+		.unwrap();
+	server_side.set_registry(Some(Arc::new(stream::Registry::default())));
+
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	assert_eq!(
+		rejection(&mut handler.0.stream).await,
+		stream::kind::code::UNKNOWN_HANDLER
+	);
+	received.assert_empty();
+
+	server_side.set_registry(None);
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler
+		.0
+		.stream
+		.write(&"accepted".to_owned())
+		.await
+		.unwrap();
+	handler.0.stream.finish().await.unwrap();
+	received.assert_next(&"accepted".to_owned()).await;
+}
+
+#[tokio::test]
+async fn unauthorized_streams_are_denied() {
+	use socknet::connection::{event::Event, Connection};
+
+	#[derive(Clone)]
+	struct Account;
+
+	let (received, messages) = testing::messages();
+	let network = Network::new(1, stream::Config::default(), |_peer| {
+		let context = Arc::new(AppContext::new(received.clone()));
+		let registry = stream::Registry::default();
+		registry.register_authorized(uni::Identifier(context), |connection: &Connection| {
+			connection.extensions().contains::<Account>()
+		});
+		registry
+	})
+	.unwrap();
+	let connection = network.connect(Peer::Client(0)).await.unwrap();
+	let server_side = testing::wait_for_created(&network.server)
+		.await
+		.unwrap()
+		.upgrade()
+		.unwrap();
+
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	assert_eq!(
+		rejection(&mut handler.0.stream).await,
+		stream::kind::code::PERMISSION_DENIED
+	);
+	match tokio::time::timeout(
+		testing::TIMEOUT,
+		network.server.connection_receiver().recv(),
+	)
+	.await
+	{
+		Ok(Ok(Event::Denied(address, handler_id))) => {
+			assert_eq!(address, network.clients[0].address());
+			assert_eq!(handler_id, "test/uni");
+		}
+		_ => panic!("expected the stream to be denied"),
+	}
+	let registry = server_side.registry().unwrap();
+	assert_eq!(registry.denials::<uni::Identifier<String>>(), 1);
+	messages.assert_empty();
+
+	server_side.extensions().insert(Account);
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler
+		.0
+		.stream
+		.write(&"authorized".to_owned())
+		.await
+		.unwrap();
+	handler.0.stream.finish().await.unwrap();
+	messages.assert_next(&"authorized".to_owned()).await;
+	assert_eq!(registry.denials::<uni::Identifier<String>>(), 1);
+}
+
+#[tokio::test]
+async fn handlers_only_accept_streams_from_their_direction() {
+	use socknet::endpoint::Role;
+
+	let (received, messages) = testing::messages();
+	let network = Network::new(1, stream::Config::default(), |_peer| {
+		let context = Arc::new(AppContext::new(received.clone()));
+		let registry = stream::Registry::default();
+		registry.register_with(uni::Identifier(context), |registration| {
+			registration.accept_from(Role::Client);
+		});
+		registry
+	})
+	.unwrap();
+	assert_eq!(network.server.role(), Role::Server);
+	assert_eq!(network.clients[0].role(), Role::Client);
+
+	// Streams the server pushes to the client are in the wrong direction.
+	let connection = network.connect(Peer::Client(0)).await.unwrap();
+	let server_side = testing::wait_for_created(&network.server).await.unwrap();
+	let mut handler = uni::Initiator::<String>::open(&server_side)
+		.unwrap()
+		.await
+		.unwrap();
+	assert_eq!(
+		rejection(&mut handler.0.stream).await,
+		stream::kind::code::WRONG_DIRECTION
+	);
+	messages.assert_empty();
+
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler
+		.0
+		.stream
+		.write(&"requested".to_owned())
+		.await
+		.unwrap();
+	handler.0.stream.finish().await.unwrap();
+	messages.assert_next(&"requested".to_owned()).await;
+
+	// The server connected to itself is also a client, so it can use the client-only handler.
+	let connection = network.connect(Peer::Server).await.unwrap();
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler.0.stream.write(&"own".to_owned()).await.unwrap();
+	handler.0.stream.finish().await.unwrap();
+	messages.assert_next(&"own".to_owned()).await;
+}
+
+#[tokio::test]
+async fn handlers_can_be_registered_while_running() {
+	use socknet::stream::Change;
+
+	let (received, messages) = testing::messages();
+	let network = Network::new(1, stream::Config::default(), |_peer| {
+		stream::Registry::default()
+	})
+	.unwrap();
+	let connection = network.connect(Peer::Client(0)).await.unwrap();
+	let server_side = testing::wait_for_created(&network.server)
+		.await
+		.unwrap()
+		.upgrade()
+		.unwrap();
+	let registry = server_side.registry().unwrap();
+	let mut changes = registry.subscribe();
+
+	// The client only needs the identifier to open the stream.
+	let context = Arc::new(AppContext::new(received));
+	connection
+		.upgrade()
+		.unwrap()
+		.registry()
+		.unwrap()
+		.register(uni::Identifier(context.clone()));
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	assert_eq!(
+		rejection(&mut handler.0.stream).await,
+		stream::kind::code::UNKNOWN_HANDLER
+	);
+
+	registry.register(uni::Identifier(context.clone()));
+	assert_eq!(
+		changes.recv().await.unwrap(),
+		Change::Registered("test/uni")
+	);
+	assert_eq!(registry.ids(), vec!["test/uni"]);
+	registry.register(uni::Identifier(context));
+	assert_eq!(changes.recv().await.unwrap(), Change::Replaced("test/uni"));
+	assert_eq!(registry.ids(), vec!["test/uni"]);
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler.0.stream.write(&"plugin".to_owned()).await.unwrap();
+	handler.0.stream.finish().await.unwrap();
+	messages.assert_next(&"plugin".to_owned()).await;
+
+	assert!(registry.unregister::<uni::Identifier<String>>());
+	assert_eq!(
+		changes.recv().await.unwrap(),
+		Change::Unregistered("test/uni")
+	);
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	assert_eq!(
+		rejection(&mut handler.0.stream).await,
+		stream::kind::code::UNKNOWN_HANDLER
+	);
+	messages.assert_empty();
+}
+
+/// Logs every stream an interceptor sees, in the order the interceptors were called.
+struct Log(&'static str, Arc<std::sync::Mutex<Vec<String>>>);
+impl stream::Interceptor for Log {
+	fn receive(&self, stream: &stream::Interception) -> stream::Flow {
+		let entry = match stream.rejection {
+			Some(code) => format!(
+				"{} received {} {:?} rejected with {}",
+				self.0, stream.handler_id, stream.kind, code
+			),
+			None => format!(
+				"{} received {} {:?}",
+				self.0, stream.handler_id, stream.kind
+			),
+		};
+		self.1.lock().unwrap().push(entry);
+		stream::Flow::Continue
+	}
+	fn open(&self, stream: &stream::Interception) -> stream::Flow {
+		let entry = format!("{} opened {} {:?}", self.0, stream.handler_id, stream.kind);
+		self.1.lock().unwrap().push(entry);
+		stream::Flow::Continue
+	}
+}
+
+#[tokio::test]
+async fn interceptors_wrap_every_handler() {
+	use socknet::stream::{capture::Kind, Flow, Interception, Interceptor};
+	use std::sync::Mutex;
+
+	/// Rejects streams of bidirectional handlers.
+	struct NoBidirectional;
+	impl Interceptor for NoBidirectional {
+		fn receive(&self, stream: &Interception) -> Flow {
+			match stream.kind {
+				Kind::Bidirectional => Flow::Reject(stream::kind::code::application(42)),
+				_ => Flow::Continue,
+			}
+		}
+		fn open(&self, stream: &Interception) -> Flow {
+			self.receive(stream)
+		}
+	}
+
+	let (network, received) = network(1, stream::Config::default());
+	let log = Arc::new(Mutex::new(Vec::new()));
+	let server_log = Log("server", log.clone());
+	network.server.stream_registry().intercept(server_log);
+	network.server.stream_registry().intercept(NoBidirectional);
+	network.clients[0]
+		.stream_registry()
+		.intercept(Log("client", log.clone()));
+
+	let connection = network.connect(Peer::Client(0)).await.unwrap();
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler.0.stream.write(&"logged".to_owned()).await.unwrap();
+	handler.0.stream.finish().await.unwrap();
+	received.assert_next(&"logged".to_owned()).await;
+
+	// Bidirectional streams are rejected by the server, after being logged.
+	let mut handler = bi::Handler::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	assert_eq!(
+		rejection(&mut handler.0.stream).await,
+		stream::kind::code::application(42)
+	);
+	received.assert_empty();
+
+	assert_eq!(
+		*log.lock().unwrap(),
+		vec![
+			"client opened test/uni Unidirectional",
+			"server received test/uni Unidirectional",
+			"client opened test/bi Bidirectional",
+			"server received test/bi Bidirectional",
+		]
+	);
+
+	// The server cannot open bidirectional streams either.
+	let local = network.connect(Peer::Server).await.unwrap();
+	let opened = bi::Handler::<String>::open(&local).unwrap().await;
+	assert!(matches!(
+		opened.err().unwrap().downcast_ref::<stream::Error>(),
+		Some(stream::Error::Intercepted("test/bi", code)) if *code == stream::kind::code::application(42)
+	));
+}
+
+#[tokio::test]
+async fn interceptors_see_rejected_streams_and_outlive_scopes() {
+	use std::sync::Mutex;
+
+	let (network, received) = network(1, stream::Config::default());
+	let log = Arc::new(Mutex::new(Vec::new()));
+	network
+		.server
+		.stream_registry()
+		.intercept(Log("endpoint", log.clone()));
+	let connection = network.connect(Peer::Client(0)).await.unwrap();
+	let server_side = testing::wait_for_created(&network.server)
+		.await
+		.unwrap()
+		.upgrade()
+		.unwrap();
+
+	// The scope has no handlers, so the stream is rejected, but both registries' interceptors see it.
+	let scope = stream::Registry::default();
+	scope.intercept(Log("scope", log.clone()));
+	server_side.set_registry(Some(Arc::new(scope)));
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	assert_eq!(
+		rejection(&mut handler.0.stream).await,
+		stream::kind::code::UNKNOWN_HANDLER
+	);
+	received.assert_empty();
+	assert_eq!(
+		*log.lock().unwrap(),
+		vec![
+			"endpoint received test/uni Unidirectional rejected with 2",
+			"scope received test/uni Unidirectional rejected with 2",
+		]
+	);
+	log.lock().unwrap().clear();
+
+	// Streams opened within a scope are seen by both registries' interceptors as well.
+	let (scope_received, _scope_messages) = testing::messages::<String>();
+	let scope = common::registry(Arc::new(AppContext::new(scope_received)));
+	scope.intercept(Log("scope", log.clone()));
+	server_side.set_registry(Some(Arc::new(scope)));
+	let mut handler = uni::Initiator::<String>::open(&Arc::downgrade(&server_side))
+		.unwrap()
+		.await
+		.unwrap();
+	handler.0.stream.write(&"pushed".to_owned()).await.unwrap();
+	received.assert_next(&"pushed".to_owned()).await;
+	assert_eq!(
+		*log.lock().unwrap(),
+		vec![
+			"endpoint opened test/uni Unidirectional",
+			"scope opened test/uni Unidirectional",
+		]
+	);
+}
+
+#[tokio::test]
+async fn interceptors_are_completed_with_the_outcome_of_each_stream() {
+	use socknet::stream::{capture::Kind, Flow, Interception, Interceptor, Outcome};
+	use std::{sync::Mutex, time::Duration};
+
+	/// Records how each stream ended, and rejects bidirectional streams from the peer.
+	struct Outcomes(&'static str, Arc<Mutex<Vec<String>>>);
+	impl Interceptor for Outcomes {
+		fn receive(&self, stream: &Interception) -> Flow {
+			match stream.kind {
+				Kind::Bidirectional => Flow::Reject(stream::kind::code::application(7)),
+				_ => Flow::Continue,
+			}
+		}
+		fn completed(&self, stream: &Interception, outcome: &Outcome, _elapsed: Duration) {
+			let outcome = match outcome {
+				Outcome::Failed(_) => "Failed".to_owned(),
+				outcome => format!("{:?}", outcome),
+			};
+			let entry = format!("{} {} {}", self.0, stream.handler_id, outcome);
+			self.1.lock().unwrap().push(entry);
+		}
+	}
+
+	let (network, received) = network(1, stream::Config::default());
+	let log = Arc::new(Mutex::new(Vec::new()));
+	network
+		.server
+		.stream_registry()
+		.intercept(Outcomes("server", log.clone()));
+	network.clients[0]
+		.stream_registry()
+		.intercept(Outcomes("client", log.clone()));
+	let connection = network.connect(Peer::Client(0)).await.unwrap();
+
+	// The receiver completes once it is done with the stream.
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler.0.stream.write(&"done".to_owned()).await.unwrap();
+	handler.0.stream.finish().await.unwrap();
+	received.assert_next(&"done".to_owned()).await;
+	drop(handler);
+	tokio::time::timeout(testing::TIMEOUT, async {
+		while log.lock().unwrap().len() < 2 {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		}
+	})
+	.await
+	.expect("timed out waiting for the receiver to complete");
+	assert_eq!(
+		*log.lock().unwrap(),
+		vec!["client test/uni Opened", "server test/uni Received"]
+	);
+	log.lock().unwrap().clear();
+
+	// Rejected streams complete right away.
+	let mut handler = bi::Handler::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	assert_eq!(
+		rejection(&mut handler.0.stream).await,
+		stream::kind::code::application(7)
+	);
+	assert_eq!(
+		*log.lock().unwrap(),
+		vec![
+			"client test/bi Opened".to_owned(),
+			format!(
+				"server test/bi Rejected({})",
+				stream::kind::code::application(7)
+			),
+		]
+	);
+}
+
+/// Creates a network whose server restricts the unidirectional handler, and reports every message it receives.
+fn restricted_network<F>(restrict: F) -> (Network, Messages<String>)
+where
+	F: Fn(&mut stream::Registration),
+{
+	let (received, messages) = testing::messages();
+	let network = Network::new(1, stream::Config::default(), |peer| {
+		let context = Arc::new(AppContext::new(received.clone()));
+		let registry = stream::Registry::default();
+		registry.register_with(uni::Identifier(context), |registration| {
+			if peer == Peer::Server {
+				restrict(registration);
+			}
+		});
+		registry
+	})
+	.unwrap();
+	(network, messages)
+}
+
+#[tokio::test]
+async fn streams_over_the_handler_limit_are_reset() {
+	use socknet::connection::event::Event;
+
+	let (network, received) = restricted_network(|registration| {
+		registration.limit(stream::Limits {
+			max_receivers: Some(1),
+			overflow: stream::Overflow::Reset,
+			..Default::default()
+		});
+	});
+	let connection = network.connect(Peer::Client(0)).await.unwrap();
+	testing::wait_for_created(&network.server).await.unwrap();
+
+	let mut active = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	active.0.stream.write(&"active".to_owned()).await.unwrap();
+	received.assert_next(&"active".to_owned()).await;
+
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	assert_eq!(
+		rejection(&mut handler.0.stream).await,
+		stream::kind::code::LIMIT_EXCEEDED
+	);
+	match tokio::time::timeout(
+		testing::TIMEOUT,
+		network.server.connection_receiver().recv(),
+	)
+	.await
+	{
+		Ok(Ok(Event::Limited(address, handler_id))) => {
+			assert_eq!(address, network.clients[0].address());
+			assert_eq!(handler_id, "test/uni");
+		}
+		_ => panic!("expected the stream to be limited"),
+	}
+	received.assert_empty();
+}
+
+#[tokio::test]
+async fn streams_over_the_connection_limit_are_queued() {
+	let (network, received) = restricted_network(|_registration| {});
+	network
+		.server
+		.stream_registry()
+		.limit_connections(stream::Limits {
+			max_receivers: Some(1),
+			max_queued: 1,
+			overflow: stream::Overflow::Queue,
+			..Default::default()
+		});
+	let connection = network.connect(Peer::Client(0)).await.unwrap();
+
+	let mut first = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	first.0.stream.write(&"first".to_owned()).await.unwrap();
+	received.assert_next(&"first".to_owned()).await;
+
+	let mut second = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	second.0.stream.write(&"second".to_owned()).await.unwrap();
+	second.0.stream.finish().await.unwrap();
+	tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+	received.assert_empty();
+
+	// The queue is full, so the third stream is reset.
+	let mut third = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	assert_eq!(
+		rejection(&mut third.0.stream).await,
+		stream::kind::code::LIMIT_EXCEEDED
+	);
+
+	// The second stream is handed to its receiver once the first receiver is done.
+	first.0.stream.finish().await.unwrap();
+	received.assert_next(&"second".to_owned()).await;
+}
+
+#[tokio::test]
+async fn streams_over_the_rate_limit_are_dropped() {
+	let (network, received) = restricted_network(|registration| {
+		registration.limit(stream::Limits {
+			rate: Some(stream::Rate {
+				per_second: 0.0,
+				burst: 1,
+			}),
+			overflow: stream::Overflow::Drop,
+			..Default::default()
+		});
+	});
+	let connection = network.connect(Peer::Client(0)).await.unwrap();
+	for message in MESSAGES.iter() {
+		let mut handler = uni::Initiator::<String>::open(&connection)
+			.unwrap()
+			.await
+			.unwrap();
+		// Dropped streams are stopped by the server, so later writes may fail.
+		let _ = handler.0.stream.write(&message.to_string()).await;
+		let _ = handler.0.stream.finish().await;
+	}
+	received.assert_next(&MESSAGES[0].to_string()).await;
+	tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+	received.assert_empty();
+}
+
+/// Opens a stream of each kind to a server whose handlers time out, but never use their streams again
+/// after receiving them, so only the deadline can stop and reset the streams.
+async fn unused_streams_time_out(peer: Peer) {
+	use socknet::stream::{
+		handler::Initiator,
+		kind::{Read, Write},
+		TimedOut,
+	};
+
+	let timeout = stream::Timeout {
+		idle: Some(std::time::Duration::from_millis(100)),
+		..Default::default()
+	};
+	let (received, _messages) = testing::messages::<String>();
+	let (streams, held_streams) = async_channel::unbounded();
+	let (echoes, held_echoes) = async_channel::unbounded();
+	let network = Network::new(1, stream::Config::default(), |peer| {
+		let mut context = AppContext::new(received.clone());
+		if peer == Peer::Server {
+			context.streams = Some(streams.clone());
+			context.echoes = Some(echoes.clone());
+		}
+		let context = Arc::new(context);
+		let registry = stream::Registry::default();
+		registry.register_with(uni::Identifier(context.clone()), |registration| {
+			registration.timeout(timeout);
+		});
+		registry.register_with(
+			bi::Identifier(Arc::new(bi::Echo(context))),
+			|registration| {
+				registration.timeout(timeout);
+			},
+		);
+		registry
+	})
+	.unwrap();
+	let connection = network.connect(peer).await.unwrap();
+
+	let mut uni = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	uni.0.stream.write(&"held".to_owned()).await.unwrap();
+	let _uni_receiver = held_streams.recv().await.unwrap();
+	let mut bi = bi::Handler::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	bi.0.stream.write(&"held".to_owned()).await.unwrap();
+	let _bi_receiver = held_echoes.recv().await.unwrap();
+
+	// The receivers still hold their streams, which were stopped (and reset) when they timed out.
+	let stopped = tokio::time::timeout(testing::TIMEOUT, async {
+		loop {
+			if let Err(error) = uni.0.stream.write(&"ignored".to_owned()).await {
+				return error;
+			}
+			tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+		}
+	})
+	.await
+	.expect("the stream was never stopped");
+	assert_eq!(stopped.downcast_ref(), Some(&TimedOut::Peer));
+	let reset = tokio::time::timeout(testing::TIMEOUT, bi.0.stream.read::<String>())
+		.await
+		.expect("the stream was never reset")
+		.unwrap_err();
+	assert_eq!(reset.downcast_ref(), Some(&TimedOut::Peer));
+}
+
+#[tokio::test]
+async fn unused_streams_time_out_remote() {
+	unused_streams_time_out(Peer::Client(0)).await;
+}
+
+#[tokio::test]
+async fn unused_streams_time_out_local() {
+	unused_streams_time_out(Peer::Server).await;
+}
+
+#[tokio::test]
+async fn idle_receivers_time_out() {
+	use socknet::connection::event::Event;
+
+	let (network, received) = restricted_network(|registration| {
+		registration.timeout(stream::Timeout {
+			idle: Some(std::time::Duration::from_millis(100)),
+			..Default::default()
+		});
+	});
+	let connection = network.connect(Peer::Client(0)).await.unwrap();
+	testing::wait_for_created(&network.server).await.unwrap();
+
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler.0.stream.write(&"active".to_owned()).await.unwrap();
+	received.assert_next(&"active".to_owned()).await;
+
+	match tokio::time::timeout(
+		testing::TIMEOUT,
+		network.server.connection_receiver().recv(),
+	)
+	.await
+	{
+		Ok(Ok(Event::TimedOut(address, handler_id))) => {
+			assert_eq!(address, network.clients[0].address());
+			assert_eq!(handler_id, "test/uni");
+		}
+		_ => panic!("expected the receiver to time out"),
+	}
+	let stopped = tokio::time::timeout(testing::TIMEOUT, async {
+		loop {
+			if let Err(error) = handler.0.stream.write(&"ignored".to_owned()).await {
+				return error;
+			}
+			tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+		}
+	})
+	.await
+	.unwrap();
+	assert_eq!(
+		stopped.downcast_ref::<stream::TimedOut>(),
+		Some(&stream::TimedOut::Peer)
+	);
+	received.assert_empty();
+}
+
+#[tokio::test]
+async fn local_receivers_time_out() {
+	use socknet::connection::event::Event;
+
+	let (network, received) = restricted_network(|registration| {
+		registration.timeout(stream::Timeout {
+			total: Some(std::time::Duration::from_millis(100)),
+			..Default::default()
+		});
+	});
+	let connection = network.connect(Peer::Server).await.unwrap();
+	testing::wait_for_created(&network.server).await.unwrap();
+
+	let mut handler = uni::Initiator::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler.0.stream.write(&"active".to_owned()).await.unwrap();
+	received.assert_next(&"active".to_owned()).await;
+	match tokio::time::timeout(
+		testing::TIMEOUT,
+		network.server.connection_receiver().recv(),
+	)
+	.await
+	{
+		Ok(Ok(Event::TimedOut(_, handler_id))) => assert_eq!(handler_id, "test/uni"),
+		_ => panic!("expected the receiver to time out"),
+	}
+	// The receiver has stopped reading, so later messages are never received.
+	let _ = handler.0.stream.write(&"late".to_owned()).await;
+	tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+	received.assert_empty();
+}
+
+async fn read_timeout(peer: Peer) {
+	use std::time::Duration;
+
+	let (network, received) = network(1, stream::Config::default());
+	let connection = network.connect(peer).await.unwrap();
+	let mut handler = bi::Handler::<String>::open(&connection)
+		.unwrap()
+		.await
+		.unwrap();
+	handler.0.stream.write(&"echo".to_owned()).await.unwrap();
+	received.assert_next(&"echo".to_owned()).await;
+	let echo = handler.0.stream.read_timeout::<String>(testing::TIMEOUT);
+	assert_eq!(echo.await.unwrap(), "echo");
+
+	// Nothing else is echoed, so the read times out and the stream can no longer be read.
+	let timed_out = handler
+		.0
+		.stream
+		.read_timeout::<String>(Duration::from_millis(50))
+		.await
+		.unwrap_err();
+	assert_eq!(
+		timed_out.downcast_ref::<stream::TimedOut>(),
+		Some(&stream::TimedOut::Deadline)
+	);
+	handler.0.stream.write(&"late".to_owned()).await.unwrap();
+	let after = tokio::time::timeout(testing::TIMEOUT, handler.0.stream.read::<String>());
+	assert!(after.await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn read_timeout_remote() {
+	read_timeout(Peer::Client(0)).await;
+}
+
+#[tokio::test]
+async fn read_timeout_local() {
+	read_timeout(Peer::Server).await;
+}
+
+#[tokio::test]
+async fn peers_receive_the_close_code_and_reason() {
+	use socknet::connection::{event::Event, Active, CloseCode, Closed, Connection};
+
+	let (network, _received) = network(1, stream::Config::default());
+	let connection = network.connect(Peer::Client(0)).await.unwrap();
+	let connection = Connection::upgrade(&connection).unwrap();
+	let server_view = testing::wait_for_created(&network.server).await.unwrap();
+	let server_view = Connection::upgrade(&server_view).unwrap();
+
+	server_view.close(CloseCode::Kicked, "spamming");
+	let closed = tokio::time::timeout(testing::TIMEOUT, async {
+		loop {
+			if let Event::Closed(address, closed) =
+				network.clients[0].connection_receiver().recv().await?
+			{
+				return anyhow::Ok((address, closed));
+			}
+		}
+	})
+	.await
+	.expect("timed out waiting for the connection to be closed")
+	.unwrap();
+	assert_eq!(
+		closed,
+		(
+			network.server.address(),
+			Closed {
+				code: CloseCode::Kicked,
+				reason: "spamming".to_owned(),
+				by_peer: true,
+			}
+		)
+	);
+	assert_eq!(closed.1.to_string(), "Kicked: spamming");
+
+	// Each side remembers how the connection was closed.
+	assert_eq!(
+		server_view.close_reason().map(|closed| closed.by_peer),
+		Some(false)
+	);
+	assert_eq!(connection.close_reason(), Some(closed.1));
+}
+
+#[tokio::test]
+async fn reserved_close_codes_are_normalized_and_reported_once() {
+	use socknet::connection::{event::Event, Active, CloseCode, Closed, Connection};
+
+	assert_eq!(CloseCode::Reserved(5).normalized(), CloseCode::Kicked);
+	assert_eq!(CloseCode::Reserved(6).normalized(), CloseCode::Reserved(6));
+	assert_eq!(
+		CloseCode::from(CloseCode::Reserved(3).into_inner()),
+		CloseCode::Unauthorized
+	);
+	// Codes beyond the application range are kept as they were received.
+	let beyond = CloseCode::APPLICATION_START + u32::MAX as u64 + 1;
+	assert_eq!(CloseCode::from(beyond), CloseCode::Unknown(beyond));
+	assert_eq!(CloseCode::from(beyond).into_inner(), beyond);
+	assert_eq!(
+		CloseCode::from(beyond - 1),
+		CloseCode::Application(u32::MAX)
+	);
+
+	let (network, _received) = network(1, stream::Config::default());
+	let connection = network.connect(Peer::Client(0)).await.unwrap();
+	let connection = Connection::upgrade(&connection).unwrap();
+	let server_view = testing::wait_for_created(&network.server).await.unwrap();
+	let server_view = Connection::upgrade(&server_view).unwrap();
+
+	server_view.close(CloseCode::Reserved(5), "spamming");
+	async fn closed(endpoint: &socknet::endpoint::Endpoint) -> anyhow::Result<Closed> {
+		loop {
+			if let Event::Closed(_, closed) = endpoint.connection_receiver().recv().await? {
+				return Ok(closed);
+			}
+		}
+	}
+	let by_client = tokio::time::timeout(testing::TIMEOUT, closed(&network.clients[0]))
+		.await
+		.expect("timed out waiting for the client to see the close")
+		.unwrap();
+	assert_eq!(by_client.code, CloseCode::Kicked);
+	assert!(by_client.by_peer);
+	assert_eq!(connection.close_reason(), Some(by_client));
+
+	let by_server = tokio::time::timeout(testing::TIMEOUT, closed(&network.server))
+		.await
+		.expect("timed out waiting for the server to see the close")
+		.unwrap();
+	assert_eq!(by_server.code, CloseCode::Kicked);
+	assert!(!by_server.by_peer);
+	assert_eq!(server_view.close_reason(), Some(by_server));
+
+	// The streams of the closed connection end with an error, which must not be reported again.
+	assert!(tokio::time::timeout(
+		std::time::Duration::from_millis(100),
+		closed(&network.server)
+	)
+	.await
+	.is_err());
+}